@@ -0,0 +1,22 @@
+use anyhow::{bail, Context, Result};
+
+/// Parses a UI amount such as `"1.5"` into base units of a mint with
+/// `decimals`.
+pub fn parse_amount(amount: &str, decimals: u8) -> Result<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+    if whole.is_empty() && fraction.is_empty() {
+        bail!("invalid amount {:?}", amount);
+    }
+    if fraction.len() > decimals as usize {
+        bail!("{:?} has more than {} decimals", amount, decimals);
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        bail!("invalid amount {:?}", amount);
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    digits
+        .parse::<u64>()
+        .with_context(|| format!("{:?} is too large", amount))
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(name = "escrow", version, about = "Make, take and inspect anchor-escrow escrows")]
+pub struct Cli {
+    /// RPC endpoint, ignored with `--litesvm`
+    #[arg(long, global = true, env = "ESCROW_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    pub url: String,
+    /// Keypair file that signs and pays for transactions
+    #[arg(long, global = true, default_value = "~/.config/solana/id.json")]
+    pub keypair: String,
+    /// Simulate transactions instead of sending them
+    #[arg(long, global = true)]
+    pub simulate: bool,
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Run against an in-process LiteSVM whose accounts persist in this file
+    #[arg(long, global = true, value_name = "STATE_FILE")]
+    pub litesvm: Option<PathBuf>,
+    /// Program binary loaded by `--litesvm`
+    #[arg(long, global = true, default_value = "target/deploy/anchor_escrow.so")]
+    pub program: PathBuf,
+    /// Advance the `--litesvm` clock by this many slots before running, so
+    /// lock periods and expiries can pass
+    #[arg(long, global = true, default_value_t = 0)]
+    pub warp_slots: u64,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Escrow `deposit` of mint A in exchange for `receive` of mint B
+    Make {
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        /// Amount of mint A, in UI units
+        #[arg(long)]
+        deposit: String,
+        /// Amount of mint B, in UI units
+        #[arg(long)]
+        receive: String,
+        /// Defaults to the current time in nanoseconds
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, default_value_t = 0)]
+        lock_period: u64,
+    },
+    /// Fill an escrow
+    Take {
+        escrow: Pubkey,
+        /// Fail instead of paying more than this much mint B, in UI units
+        #[arg(long)]
+        max_price: Option<String>,
+        /// Holder of the escrow's position token, if it has one
+        #[arg(long)]
+        position_holder: Option<Pubkey>,
+    },
+    /// Return an escrow's vault to the signer and close it
+    Refund {
+        escrow: Pubkey,
+    },
+    /// Show an escrow's terms
+    Show {
+        escrow: Pubkey,
+    },
+    /// List escrows by maker or mint
+    List {
+        #[arg(long)]
+        maker: Option<Pubkey>,
+        /// Matches either side of the escrow
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Create a mint with the signer as authority and mint to their ATA
+    CreateMint {
+        #[arg(long, default_value_t = 6)]
+        decimals: u8,
+        /// Amount to mint to the signer, in UI units
+        #[arg(long, default_value = "0")]
+        amount: String,
+    },
+}
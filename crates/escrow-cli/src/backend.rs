@@ -0,0 +1,80 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, Discriminator};
+use anchor_spl::token_2022::spl_token_2022::{extension::StateWithExtensions, state::{Account as TokenAccount, Mint}};
+use anyhow::{anyhow, Context, Result};
+use escrow_client::{decode_escrow, Escrow};
+use solana_account::Account;
+use solana_keypair::Keypair;
+
+/// Where transactions are sent and accounts read from.
+pub trait Backend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>>;
+
+    /// Every escrow account matching `filter`.
+    fn escrows(&self, filter: &EscrowFilter) -> Result<Vec<(Pubkey, Escrow)>>;
+
+    fn minimum_balance_for_rent_exemption(&self, len: usize) -> Result<u64>;
+
+    /// Signs with `payer` and any extra `signers`, then sends, or only
+    /// simulates when `simulate` is set.
+    fn send(&mut self, ixs: &[Instruction], payer: &Keypair, signers: &[&Keypair], simulate: bool) -> Result<TxOutcome>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TxOutcome {
+    /// `None` for simulations.
+    pub signature: Option<String>,
+    pub logs: Vec<String>,
+    pub compute_units: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EscrowFilter {
+    pub maker: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+}
+
+impl EscrowFilter {
+    pub fn matches(&self, escrow: &Escrow) -> bool {
+        self.maker.is_none_or(|maker| escrow.maker == maker)
+            && self.mint.is_none_or(|mint| escrow.mint_a == mint || escrow.mint_b == mint)
+    }
+}
+
+/// Size of an escrow account, discriminator included.
+pub const ESCROW_ACCOUNT_LEN: usize = 8 + Escrow::LEN;
+
+/// Decodes `account` if it is an escrow owned by the program.
+pub fn as_escrow(account: &Account) -> Option<Escrow> {
+    if account.owner != escrow_client::PROGRAM_ID || !account.data.starts_with(Escrow::DISCRIMINATOR) {
+        return None;
+    }
+
+    decode_escrow(&account.data).ok()
+}
+
+pub fn fetch(backend: &dyn Backend, address: &Pubkey, what: &str) -> Result<Account> {
+    backend
+        .get_account(address)?
+        .ok_or_else(|| anyhow!("{} {} not found", what, address))
+}
+
+pub fn fetch_escrow(backend: &dyn Backend, address: &Pubkey) -> Result<Escrow> {
+    let account = fetch(backend, address, "escrow")?;
+    as_escrow(&account).ok_or_else(|| anyhow!("{} is not an escrow", address))
+}
+
+/// Decimals of a Token or Token-2022 mint.
+pub fn mint_decimals(account: &Account) -> Result<u8> {
+    let mint = StateWithExtensions::<Mint>::unpack(&account.data).context("invalid mint account")?;
+    Ok(mint.base.decimals)
+}
+
+/// Balance of a Token or Token-2022 account, zero if it does not exist.
+pub fn token_balance(backend: &dyn Backend, address: &Pubkey) -> Result<u64> {
+    let Some(account) = backend.get_account(address)? else {
+        return Ok(0);
+    };
+
+    let token_account = StateWithExtensions::<TokenAccount>::unpack(&account.data).context("invalid token account")?;
+    Ok(token_account.base.amount)
+}
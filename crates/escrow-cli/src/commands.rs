@@ -0,0 +1,203 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use anchor_lang::{prelude::Pubkey, solana_program::{instruction::Instruction, program_pack::Pack}};
+use anchor_spl::{associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent, token_2022::spl_token_2022};
+use anyhow::{bail, Result};
+use escrow_client::{associated_token_address, token_program_for_mint, vault_address, Escrow, EscrowSummary, MakeBuilder, RefundBuilder, TakeBuilder};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_system_interface::instruction::create_account;
+
+use crate::amount::parse_amount;
+use crate::args::Command;
+use crate::backend::{fetch, fetch_escrow, mint_decimals, token_balance, Backend, EscrowFilter, TxOutcome};
+
+/// Result of a command, printed as text or JSON.
+#[derive(Debug)]
+pub enum Report {
+    Made { escrow: Pubkey, tx: TxOutcome },
+    Taken { escrow: Pubkey, tx: TxOutcome },
+    Refunded { escrow: Pubkey, tx: TxOutcome },
+    MintCreated { mint: Pubkey, tx: TxOutcome },
+    Escrow(EscrowSummary),
+    Escrows(Vec<EscrowSummary>),
+}
+
+pub fn run(command: &Command, backend: &mut dyn Backend, signer: &Keypair, simulate: bool) -> Result<Report> {
+    match command {
+        Command::Make { mint_a, mint_b, deposit, receive, seed, lock_period } => {
+            let mint_a_account = fetch(backend, mint_a, "mint")?;
+            let mint_b_account = fetch(backend, mint_b, "mint")?;
+            let token_program = token_program_for_mint(mint_a, &mint_a_account.owner)?;
+            let seed = seed.unwrap_or_else(default_seed);
+
+            let builder = MakeBuilder::new(signer.pubkey(), seed, *mint_a, *mint_b, token_program)
+                .deposit(parse_amount(deposit, mint_decimals(&mint_a_account)?)?)
+                .receive(parse_amount(receive, mint_decimals(&mint_b_account)?)?)
+                .lock_period(*lock_period);
+
+            let tx = backend.send(&[builder.instruction()], signer, &[], simulate)?;
+            Ok(Report::Made { escrow: builder.escrow(), tx })
+        }
+        Command::Take { escrow: address, max_price, position_holder } => {
+            let escrow = fetch_escrow(backend, address)?;
+            let token_program = token_program_for_mint(&escrow.mint_a, &fetch(backend, &escrow.mint_a, "mint")?.owner)?;
+
+            let mut builder = TakeBuilder::new(signer.pubkey(), *address, &escrow, token_program);
+            if let Some(holder) = position_holder {
+                builder = builder.position_holder(*holder);
+            }
+            if backend
+                .get_account(&escrow.proceeds_recipient)?
+                .is_some_and(|account| account.owner == token_program)
+            {
+                builder = builder.proceeds_token_account();
+            }
+            if let Some(max_price) = max_price {
+                let decimals_b = mint_decimals(&fetch(backend, &escrow.mint_b, "mint")?)?;
+                let vault_amount = token_balance(backend, &vault_address(address, &escrow.mint_a, &token_program))?;
+                builder = builder.guard(parse_amount(max_price, decimals_b)?, vault_amount);
+            }
+
+            let tx = backend.send(&[builder.instruction()?], signer, &[], simulate)?;
+            Ok(Report::Taken { escrow: *address, tx })
+        }
+        Command::Refund { escrow: address } => {
+            let escrow = fetch_escrow(backend, address)?;
+            let token_program = token_program_for_mint(&escrow.mint_a, &fetch(backend, &escrow.mint_a, "mint")?.owner)?;
+
+            let ix = RefundBuilder::new(signer.pubkey(), *address, &escrow, token_program).instruction();
+
+            let tx = backend.send(&[ix], signer, &[], simulate)?;
+            Ok(Report::Refunded { escrow: *address, tx })
+        }
+        Command::Show { escrow: address } => {
+            let escrow = fetch_escrow(backend, address)?;
+            let mut decimals = HashMap::new();
+            Ok(Report::Escrow(summarize(backend, *address, &escrow, &mut decimals)?))
+        }
+        Command::List { maker, mint } => {
+            if maker.is_none() && mint.is_none() {
+                bail!("pass --maker, --mint or both");
+            }
+
+            let filter = EscrowFilter { maker: *maker, mint: *mint };
+            let mut decimals = HashMap::new();
+            let summaries = backend
+                .escrows(&filter)?
+                .iter()
+                .map(|(address, escrow)| summarize(backend, *address, escrow, &mut decimals))
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(Report::Escrows(summaries))
+        }
+        Command::CreateMint { decimals, amount } => {
+            let mint = Keypair::new();
+            let amount = parse_amount(amount, *decimals)?;
+            let ixs = create_mint_ixs(backend, signer, &mint.pubkey(), *decimals, amount)?;
+
+            let tx = backend.send(&ixs, signer, &[&mint], simulate)?;
+            Ok(Report::MintCreated { mint: mint.pubkey(), tx })
+        }
+    }
+}
+
+fn default_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+fn summarize(backend: &dyn Backend, address: Pubkey, escrow: &Escrow, decimals: &mut HashMap<Pubkey, u8>) -> Result<EscrowSummary> {
+    let mint_a = fetch(backend, &escrow.mint_a, "mint")?;
+    let token_program = token_program_for_mint(&escrow.mint_a, &mint_a.owner)?;
+
+    for mint in [escrow.mint_a, escrow.mint_b] {
+        if let Entry::Vacant(entry) = decimals.entry(mint) {
+            entry.insert(mint_decimals(&fetch(backend, &mint, "mint")?)?);
+        }
+    }
+
+    let vault_amount = token_balance(backend, &vault_address(&address, &escrow.mint_a, &token_program))?;
+
+    Ok(EscrowSummary::new(address, escrow, vault_amount, decimals[&escrow.mint_a], decimals[&escrow.mint_b]))
+}
+
+fn create_mint_ixs(backend: &dyn Backend, signer: &Keypair, mint: &Pubkey, decimals: u8, amount: u64) -> Result<Vec<Instruction>> {
+    let token_program = anchor_spl::token::ID;
+    let authority = signer.pubkey();
+    let ata = associated_token_address(&authority, mint, &token_program);
+    let rent = backend.minimum_balance_for_rent_exemption(spl_token_2022::state::Mint::LEN)?;
+
+    let mut ixs = vec![
+        create_account(&authority, mint, rent, spl_token_2022::state::Mint::LEN as u64, &token_program),
+        anchor_spl::token::spl_token::instruction::initialize_mint2(&token_program, mint, &authority, None, decimals)?,
+        create_associated_token_account_idempotent(&authority, &authority, mint, &token_program),
+    ];
+    if amount > 0 {
+        ixs.push(anchor_spl::token::spl_token::instruction::mint_to(&token_program, mint, &ata, &authority, &[], amount)?);
+    }
+
+    Ok(ixs)
+}
+
+impl Report {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Report::Made { escrow, tx } => json!({ "escrow": escrow.to_string(), "tx": tx_json(tx) }),
+            Report::Taken { escrow, tx } => json!({ "escrow": escrow.to_string(), "tx": tx_json(tx) }),
+            Report::Refunded { escrow, tx } => json!({ "escrow": escrow.to_string(), "tx": tx_json(tx) }),
+            Report::MintCreated { mint, tx } => json!({ "mint": mint.to_string(), "tx": tx_json(tx) }),
+            Report::Escrow(summary) => summary_json(summary),
+            Report::Escrows(summaries) => Value::Array(summaries.iter().map(summary_json).collect()),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self {
+            Report::Made { escrow, tx } => format!("made escrow {}\n{}", escrow, tx_text(tx)),
+            Report::Taken { escrow, tx } => format!("took escrow {}\n{}", escrow, tx_text(tx)),
+            Report::Refunded { escrow, tx } => format!("refunded escrow {}\n{}", escrow, tx_text(tx)),
+            Report::MintCreated { mint, tx } => format!("created mint {}\n{}", mint, tx_text(tx)),
+            Report::Escrow(summary) => summary.to_string(),
+            Report::Escrows(summaries) if summaries.is_empty() => "no escrows found".to_string(),
+            Report::Escrows(summaries) => summaries
+                .iter()
+                .map(|summary| summary.to_string())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
+}
+
+fn tx_json(tx: &TxOutcome) -> Value {
+    json!({
+        "signature": tx.signature,
+        "simulated": tx.signature.is_none(),
+        "compute_units": tx.compute_units,
+        "logs": tx.logs,
+    })
+}
+
+fn tx_text(tx: &TxOutcome) -> String {
+    match &tx.signature {
+        Some(signature) => format!("signature {}, {} compute units", signature, tx.compute_units),
+        None => format!("simulated, {} compute units\n{}", tx.compute_units, tx.logs.join("\n")),
+    }
+}
+
+fn summary_json(summary: &EscrowSummary) -> Value {
+    json!({
+        "address": summary.address.to_string(),
+        "maker": summary.maker.to_string(),
+        "mint_a": summary.mint_a.to_string(),
+        "mint_b": summary.mint_b.to_string(),
+        "amount_a": summary.amount_a,
+        "receive": summary.receive,
+        "pricing": summary.pricing,
+        "expires_at": summary.expires_at,
+        "listed": summary.listed,
+    })
+}
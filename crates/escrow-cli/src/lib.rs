@@ -0,0 +1,17 @@
+//! The `escrow` command-line tool, split from its binary so the commands
+//! can be driven against a [`LiteSvmBackend`] in tests.
+
+pub mod amount;
+pub mod args;
+pub mod backend;
+pub mod commands;
+pub mod litesvm;
+pub mod rpc;
+#[cfg(test)]
+mod tests;
+
+pub use args::{Cli, Command};
+pub use backend::Backend;
+pub use commands::{run, Report};
+pub use litesvm::LiteSvmBackend;
+pub use rpc::RpcBackend;
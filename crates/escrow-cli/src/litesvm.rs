@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::{Path, PathBuf}};
+
+use anchor_lang::{prelude::{Clock, Pubkey}, solana_program::instruction::Instruction};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use escrow_client::Escrow;
+use litesvm::LiteSVM;
+use serde::{Deserialize, Serialize};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::backend::{as_escrow, Backend, EscrowFilter, TxOutcome};
+
+/// SOL given to a signer the state file has never seen, so demos need no
+/// setup.
+const DEMO_AIRDROP: u64 = 100_000_000_000;
+
+/// Accounts written by transactions, persisted between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    slot: u64,
+    accounts: BTreeMap<String, StoredAccount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredAccount {
+    lamports: u64,
+    owner: String,
+    executable: bool,
+    /// Base64 account data.
+    data: String,
+}
+
+/// In-process LiteSVM with `anchor_escrow` loaded, whose accounts are read
+/// from and saved back to a JSON state file. Transactions do not advance
+/// the slot, only `warp_slots` does.
+pub struct LiteSvmBackend {
+    svm: LiteSVM,
+    state_path: PathBuf,
+    accounts: BTreeMap<Pubkey, Account>,
+}
+
+impl LiteSvmBackend {
+    pub fn open(state_path: &Path, program_path: &Path) -> Result<Self> {
+        let program = fs::read(program_path)
+            .with_context(|| format!("failed to read program {}, run `anchor build` first", program_path.display()))?;
+
+        let mut svm = LiteSVM::new();
+        svm.add_program(escrow_client::PROGRAM_ID, &program)
+            .map_err(|err| anyhow!("failed to load program {}: {:?}", program_path.display(), err))?;
+
+        let state: State = match fs::read_to_string(state_path) {
+            Ok(json) => serde_json::from_str(&json).context("invalid state file")?,
+            Err(err) if err.kind() == ErrorKind::NotFound => State::default(),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {}", state_path.display())),
+        };
+
+        let mut accounts = BTreeMap::new();
+        for (address, stored) in state.accounts {
+            let address: Pubkey = address.parse().context("invalid address in state file")?;
+            let account = Account {
+                lamports: stored.lamports,
+                data: STANDARD.decode(&stored.data).context("invalid account data in state file")?,
+                owner: stored.owner.parse().context("invalid owner in state file")?,
+                executable: stored.executable,
+                rent_epoch: 0,
+            };
+            svm.set_account(address, account.clone())
+                .map_err(|err| anyhow!("failed to restore {}: {:?}", address, err))?;
+            accounts.insert(address, account);
+        }
+        if state.slot > 0 {
+            svm.warp_to_slot(state.slot);
+        }
+
+        Ok(Self {
+            svm,
+            state_path: state_path.to_path_buf(),
+            accounts,
+        })
+    }
+
+    pub fn svm(&mut self) -> &mut LiteSVM {
+        &mut self.svm
+    }
+
+    /// Airdrops to `signer` the first time it is seen.
+    pub fn fund(&mut self, signer: &Pubkey) -> Result<()> {
+        if self.svm.get_account(signer).is_some_and(|account| account.lamports > 0) {
+            return Ok(());
+        }
+
+        self.svm
+            .airdrop(signer, DEMO_AIRDROP)
+            .map_err(|err| anyhow!("airdrop failed: {:?}", err.err))?;
+        self.track(&[*signer]);
+
+        self.save()
+    }
+
+    /// Copies the current state of `addresses` from the SVM, dropping
+    /// closed accounts. Programs are reloaded on open and never stored.
+    pub fn track(&mut self, addresses: &[Pubkey]) {
+        for address in addresses {
+            match self.svm.get_account(address) {
+                Some(account) if account.lamports > 0 && !account.executable => {
+                    self.accounts.insert(*address, account);
+                }
+                _ => {
+                    self.accounts.remove(address);
+                }
+            }
+        }
+    }
+
+    /// Moves the clock `slots` ahead and saves it.
+    pub fn warp_slots(&mut self, slots: u64) -> Result<()> {
+        if slots == 0 {
+            return Ok(());
+        }
+
+        let slot = self.svm.get_sysvar::<Clock>().slot;
+        self.svm.warp_to_slot(slot.checked_add(slots).context("slot overflow")?);
+
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let state = State {
+            slot: self.svm.get_sysvar::<Clock>().slot,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(address, account)| {
+                    (address.to_string(), StoredAccount {
+                        lamports: account.lamports,
+                        owner: account.owner.to_string(),
+                        executable: account.executable,
+                        data: STANDARD.encode(&account.data),
+                    })
+                })
+                .collect(),
+        };
+
+        fs::write(&self.state_path, serde_json::to_string_pretty(&state)?)
+            .with_context(|| format!("failed to write {}", self.state_path.display()))
+    }
+}
+
+impl Backend for LiteSvmBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        Ok(self.svm.get_account(address).filter(|account| account.lamports > 0))
+    }
+
+    fn escrows(&self, filter: &EscrowFilter) -> Result<Vec<(Pubkey, Escrow)>> {
+        Ok(self
+            .accounts
+            .iter()
+            .filter_map(|(address, account)| as_escrow(account).map(|escrow| (*address, escrow)))
+            .filter(|(_, escrow)| filter.matches(escrow))
+            .collect())
+    }
+
+    fn minimum_balance_for_rent_exemption(&self, len: usize) -> Result<u64> {
+        Ok(self.svm.minimum_balance_for_rent_exemption(len))
+    }
+
+    fn send(&mut self, ixs: &[Instruction], payer: &Keypair, signers: &[&Keypair], simulate: bool) -> Result<TxOutcome> {
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &all_signers, self.svm.latest_blockhash());
+        let addresses = tx.message.account_keys.clone();
+
+        if simulate {
+            let result = self
+                .svm
+                .simulate_transaction(tx)
+                .map_err(|failed| anyhow!("simulation failed: {}\n{}", failed.err, failed.meta.logs.join("\n")))?;
+
+            return Ok(TxOutcome {
+                signature: None,
+                logs: result.meta.logs,
+                compute_units: result.meta.compute_units_consumed,
+            });
+        }
+
+        let meta = match self.svm.send_transaction(tx) {
+            Ok(meta) => meta,
+            Err(failed) => bail!("transaction failed: {}\n{}", failed.err, failed.meta.logs.join("\n")),
+        };
+
+        self.track(&addresses);
+        self.save()?;
+
+        Ok(TxOutcome {
+            signature: Some(meta.signature.to_string()),
+            logs: meta.logs,
+            compute_units: meta.compute_units_consumed,
+        })
+    }
+}
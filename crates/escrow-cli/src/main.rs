@@ -0,0 +1,49 @@
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use escrow_cli::{run, Cli, LiteSvmBackend, Report, RpcBackend};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match execute(&cli) {
+        Ok(report) => {
+            if cli.json {
+                println!("{}", report.to_json());
+            } else {
+                println!("{}", report.to_text());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn execute(cli: &Cli) -> Result<Report> {
+    let signer = load_keypair(&cli.keypair)?;
+
+    match &cli.litesvm {
+        Some(state_path) => {
+            let mut backend = LiteSvmBackend::open(state_path, &cli.program)?;
+            backend.fund(&signer.pubkey())?;
+            backend.warp_slots(cli.warp_slots)?;
+            run(&cli.command, &mut backend, &signer, cli.simulate)
+        }
+        None => run(&cli.command, &mut RpcBackend::new(&cli.url), &signer, cli.simulate),
+    }
+}
+
+fn load_keypair(path: &str) -> Result<Keypair> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME").map(|home| format!("{}/{}", home, rest)).unwrap_or_else(|_| path.to_string()),
+        None => path.to_string(),
+    };
+
+    read_keypair_file(&path).map_err(|err| anyhow!("failed to read keypair {}: {}", path, err))
+}
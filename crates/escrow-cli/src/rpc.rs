@@ -0,0 +1,147 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, Discriminator};
+use anyhow::{anyhow, bail, Context, Result};
+use escrow_client::Escrow;
+use solana_account::Account;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_keypair::Keypair;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+
+use crate::backend::{as_escrow, Backend, EscrowFilter, TxOutcome, ESCROW_ACCOUNT_LEN};
+
+// Byte offsets of the escrow fields the filters match on, after the
+// 8-byte discriminator and the `seed`.
+const MAKER_OFFSET: usize = 16;
+const MINT_A_OFFSET: usize = 48;
+const MINT_B_OFFSET: usize = 80;
+
+pub struct RpcBackend {
+    client: RpcClient,
+}
+
+impl RpcBackend {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: RpcClient::new(url.to_string()),
+        }
+    }
+
+    fn program_escrows(&self, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Escrow)>> {
+        let mut filters = filters;
+        filters.push(RpcFilterType::DataSize(ESCROW_ACCOUNT_LEN as u64));
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, Escrow::DISCRIMINATOR)));
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(&escrow_client::PROGRAM_ID, config)?;
+
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| as_escrow(&account).map(|escrow| (address, escrow)))
+            .collect())
+    }
+}
+
+impl Backend for RpcBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())?
+            .value)
+    }
+
+    fn escrows(&self, filter: &EscrowFilter) -> Result<Vec<(Pubkey, Escrow)>> {
+        let maker = filter
+            .maker
+            .map(|maker| RpcFilterType::Memcmp(Memcmp::new_base58_encoded(MAKER_OFFSET, maker.as_ref())));
+
+        // Either side can match the mint, which takes one query per side
+        let queries: Vec<Vec<RpcFilterType>> = match filter.mint {
+            Some(mint) => [MINT_A_OFFSET, MINT_B_OFFSET]
+                .into_iter()
+                .map(|offset| {
+                    let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, mint.as_ref()))];
+                    filters.extend(maker.clone());
+                    filters
+                })
+                .collect(),
+            None => vec![maker.into_iter().collect()],
+        };
+
+        let mut escrows = Vec::new();
+        for filters in queries {
+            for (address, escrow) in self.program_escrows(filters)? {
+                if !escrows.iter().any(|(listed, _)| *listed == address) {
+                    escrows.push((address, escrow));
+                }
+            }
+        }
+        escrows.sort_by_key(|(address, _)| *address);
+
+        Ok(escrows)
+    }
+
+    fn minimum_balance_for_rent_exemption(&self, len: usize) -> Result<u64> {
+        Ok(self.client.get_minimum_balance_for_rent_exemption(len)?)
+    }
+
+    fn send(&mut self, ixs: &[Instruction], payer: &Keypair, signers: &[&Keypair], simulate: bool) -> Result<TxOutcome> {
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+
+        let blockhash = self.client.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &all_signers, blockhash);
+
+        if simulate {
+            let result = self.client.simulate_transaction(&tx)?.value;
+            let logs = result.logs.unwrap_or_default();
+            if let Some(err) = result.err {
+                bail!("simulation failed: {}\n{}", err, logs.join("\n"));
+            }
+
+            return Ok(TxOutcome {
+                signature: None,
+                logs,
+                compute_units: result.units_consumed.unwrap_or_default(),
+            });
+        }
+
+        let signature = self.client.send_and_confirm_transaction(&tx)?;
+
+        // The send only returns the signature, the logs and compute units
+        // come from the confirmed transaction
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let meta = self
+            .client
+            .get_transaction_with_config(&signature, config)
+            .with_context(|| format!("failed to fetch confirmed transaction {}", signature))?
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow!("confirmed transaction {} has no status meta", signature))?;
+
+        Ok(TxOutcome {
+            signature: Some(signature.to_string()),
+            logs: Option::from(meta.log_messages).unwrap_or_default(),
+            compute_units: Option::from(meta.compute_units_consumed).unwrap_or_default(),
+        })
+    }
+}
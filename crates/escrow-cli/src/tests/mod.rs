@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::amount::parse_amount;
+use crate::backend::{token_balance, Backend};
+use crate::{run, Command, LiteSvmBackend, Report};
+
+fn program_path() -> PathBuf {
+    // Built by `anchor build` into the workspace target
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/anchor_escrow.so")
+}
+
+/// Reopens the state file for every command, as separate CLI runs would.
+fn exec(state: &Path, signer: &Keypair, command: Command) -> Report {
+    let mut backend = LiteSvmBackend::open(state, &program_path()).unwrap();
+    backend.fund(&signer.pubkey()).unwrap();
+    run(&command, &mut backend, signer, false).unwrap()
+}
+
+fn create_mint(state: &Path, signer: &Keypair, amount: &str) -> Pubkey {
+    match exec(state, signer, Command::CreateMint { decimals: 6, amount: amount.to_string() }) {
+        Report::MintCreated { mint, .. } => mint,
+        report => panic!("unexpected report {:?}", report),
+    }
+}
+
+fn make(state: &Path, maker: &Keypair, mint_a: Pubkey, mint_b: Pubkey, seed: u64) -> Pubkey {
+    let command = Command::Make {
+        mint_a,
+        mint_b,
+        deposit: "10".to_string(),
+        receive: "2.5".to_string(),
+        seed: Some(seed),
+        lock_period: 0,
+    };
+    match exec(state, maker, command) {
+        Report::Made { escrow, .. } => escrow,
+        report => panic!("unexpected report {:?}", report),
+    }
+}
+
+fn balance(state: &Path, owner: &Pubkey, mint: &Pubkey) -> u64 {
+    let backend = LiteSvmBackend::open(state, &program_path()).unwrap();
+    let ata = escrow_client::associated_token_address(owner, mint, &anchor_spl::token::ID);
+    token_balance(&backend as &dyn Backend, &ata).unwrap()
+}
+
+#[test]
+fn test_parse_amount() {
+    assert_eq!(parse_amount("1.5", 6).unwrap(), 1_500_000);
+    assert_eq!(parse_amount("2", 6).unwrap(), 2_000_000);
+    assert_eq!(parse_amount(".000001", 6).unwrap(), 1);
+    assert_eq!(parse_amount("42", 0).unwrap(), 42);
+    assert!(parse_amount("0.0000001", 6).is_err());
+    assert!(parse_amount("1.2.3", 6).is_err());
+    assert!(parse_amount("-1", 6).is_err());
+    assert!(parse_amount(".", 6).is_err());
+    assert!(parse_amount("18446744073709551616", 0).is_err());
+}
+
+#[test]
+fn test_litesvm_warp_persists() {
+    let state = std::env::temp_dir().join(format!("escrow-cli-{}.json", Pubkey::new_unique()));
+    let slot = |state: &Path| LiteSvmBackend::open(state, &program_path()).unwrap().svm().get_sysvar::<Clock>().slot;
+
+    let mut backend = LiteSvmBackend::open(&state, &program_path()).unwrap();
+    let start = backend.svm().get_sysvar::<Clock>().slot;
+    backend.warp_slots(100).unwrap();
+    assert_eq!(slot(&state), start + 100);
+
+    // Sending does not move the clock
+    let signer = Keypair::new();
+    create_mint(&state, &signer, "1");
+    assert_eq!(slot(&state), start + 100);
+
+    let _ = std::fs::remove_file(&state);
+}
+
+#[test]
+fn test_cli_make_take_refund_on_litesvm() {
+    let state = std::env::temp_dir().join(format!("escrow-cli-{}.json", Pubkey::new_unique()));
+    let (maker, taker) = (Keypair::new(), Keypair::new());
+
+    let mint_a = create_mint(&state, &maker, "100");
+    let mint_b = create_mint(&state, &taker, "100");
+    assert_eq!(balance(&state, &maker.pubkey(), &mint_a), 100_000_000);
+
+    let escrow = make(&state, &maker, mint_a, mint_b, 1);
+    assert_eq!(balance(&state, &maker.pubkey(), &mint_a), 90_000_000);
+
+    match exec(&state, &maker, Command::Show { escrow }) {
+        Report::Escrow(summary) => {
+            assert_eq!(summary.maker, maker.pubkey());
+            assert_eq!(summary.amount_a, "10");
+            assert_eq!(summary.receive, "2.5");
+        }
+        report => panic!("unexpected report {:?}", report),
+    }
+    match exec(&state, &taker, Command::List { maker: None, mint: Some(mint_b) }) {
+        Report::Escrows(summaries) => assert_eq!(summaries.iter().map(|s| s.address).collect::<Vec<_>>(), [escrow]),
+        report => panic!("unexpected report {:?}", report),
+    }
+
+    let command = Command::Take { escrow, max_price: Some("2.5".to_string()), position_holder: None };
+    assert!(matches!(exec(&state, &taker, command), Report::Taken { .. }));
+    assert_eq!(balance(&state, &taker.pubkey(), &mint_a), 10_000_000);
+    assert_eq!(balance(&state, &maker.pubkey(), &mint_b), 2_500_000);
+
+    let escrow = make(&state, &maker, mint_a, mint_b, 2);
+    assert!(matches!(exec(&state, &maker, Command::Refund { escrow }), Report::Refunded { .. }));
+    assert_eq!(balance(&state, &maker.pubkey(), &mint_a), 90_000_000);
+    match exec(&state, &maker, Command::List { maker: Some(maker.pubkey()), mint: None }) {
+        Report::Escrows(summaries) => assert!(summaries.is_empty(), "Expected every escrow to be closed"),
+        report => panic!("unexpected report {:?}", report),
+    }
+
+    let _ = std::fs::remove_file(&state);
+}
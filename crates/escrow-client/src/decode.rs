@@ -0,0 +1,101 @@
+use std::fmt;
+
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator};
+use anchor_escrow::state::{Market, Pricing};
+
+use crate::error::{ClientError, Result};
+use crate::token::format_amount;
+use crate::Escrow;
+
+/// Decodes an escrow account's data. The data is copied out, so it need
+/// not be aligned, and anything but a whole `Escrow` is an error rather
+/// than a panic.
+pub fn decode_escrow(data: &[u8]) -> Result<Escrow> {
+    let body = check_discriminator("Escrow", data, Escrow::DISCRIMINATOR)?;
+    if body.len() != Escrow::LEN {
+        return Err(ClientError::Decode {
+            account: "Escrow",
+            reason: format!("expected {} bytes of account data, got {}", 8 + Escrow::LEN, data.len()),
+        });
+    }
+
+    Ok(bytemuck::pod_read_unaligned(body))
+}
+
+pub fn decode_market(data: &[u8]) -> Result<Market> {
+    let mut body = check_discriminator("Market", data, Market::DISCRIMINATOR)?;
+
+    Market::deserialize(&mut body).map_err(|err| ClientError::Decode {
+        account: "Market",
+        reason: err.to_string(),
+    })
+}
+
+/// The account data after its 8-byte discriminator, if it matches.
+fn check_discriminator<'a>(account: &'static str, data: &'a [u8], discriminator: &[u8]) -> Result<&'a [u8]> {
+    match data.split_at_checked(discriminator.len()) {
+        Some((found, body)) if found == discriminator => Ok(body),
+        _ => Err(ClientError::Decode {
+            account,
+            reason: "account discriminator mismatch".to_string(),
+        }),
+    }
+}
+
+/// An escrow's terms with amounts scaled by their mints' decimals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowSummary {
+    pub address: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// The vault balance, in mint_a units.
+    pub amount_a: String,
+    /// The asking price, in mint_b units. Dutch auctions show their start
+    /// price and English auctions their minimum bid.
+    pub receive: String,
+    pub pricing: &'static str,
+    pub expires_at: Option<u64>,
+    pub listed: bool,
+}
+
+impl EscrowSummary {
+    pub fn new(address: Pubkey, escrow: &Escrow, vault_amount: u64, decimals_a: u8, decimals_b: u8) -> Self {
+        let pricing = match escrow.pricing() {
+            Pricing::Fixed if escrow.is_standing() => "standing",
+            Pricing::Fixed => "fixed",
+            Pricing::Dutch(_) => "dutch",
+            Pricing::English => "english",
+        };
+
+        Self {
+            address,
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            amount_a: format_amount(vault_amount, decimals_a),
+            receive: format_amount(escrow.receive, decimals_b),
+            pricing,
+            expires_at: (escrow.expires_at != 0).then_some(escrow.expires_at),
+            listed: escrow.is_listed(),
+        }
+    }
+}
+
+impl fmt::Display for EscrowSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "escrow  {}", self.address)?;
+        writeln!(f, "maker   {}", self.maker)?;
+        writeln!(f, "offers  {} of {}", self.amount_a, self.mint_a)?;
+        writeln!(f, "for     {} of {}", self.receive, self.mint_b)?;
+        write!(f, "pricing {}", self.pricing)?;
+        if let Some(expires_at) = self.expires_at {
+            write!(f, ", expires at slot {}", expires_at)?;
+        }
+        if self.listed {
+            write!(f, ", listed")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account {0} is not owned by the Token or Token-2022 program")]
+    NotATokenAccount(Pubkey),
+
+    #[error("failed to decode {account}: {reason}")]
+    Decode { account: &'static str, reason: String },
+
+    #[error("escrow has a position token, pass its holder")]
+    MissingPositionHolder,
+
+    #[error("{0} does not pay a referrer, drop the referrer or the option that selects it")]
+    ReferrerNotSupported(&'static str),
+
+    #[error(transparent)]
+    Program(#[from] anchor_lang::error::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
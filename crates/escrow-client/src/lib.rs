@@ -0,0 +1,27 @@
+//! Instruction builders and account decoding for the `anchor_escrow`
+//! program.
+//!
+//! Builders derive every account an instruction needs from the maker, seed
+//! and mints, or from a decoded [`Escrow`], so callers only supply the keys
+//! the program cannot derive.
+
+pub mod decode;
+pub mod error;
+pub mod make;
+pub mod pda;
+pub mod refund;
+pub mod take;
+pub mod token;
+#[cfg(test)]
+mod tests;
+
+pub use anchor_escrow::state::Escrow;
+pub use anchor_escrow::ID as PROGRAM_ID;
+
+pub use decode::*;
+pub use error::*;
+pub use make::*;
+pub use pda::*;
+pub use refund::*;
+pub use take::*;
+pub use token::*;
@@ -0,0 +1,175 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::{AccountMeta, Instruction}, system_program, InstructionData, ToAccountMetas};
+use anchor_escrow::{instructions::EscrowReference, state::RecoveryConfig};
+
+use crate::pda::{associated_token_address, escrow_address, market_address, position_mint_address, vault_address};
+use crate::PROGRAM_ID;
+
+/// Builds a fixed-price `make`. Every account is derived from the maker,
+/// seed and mints; the options below only add optional accounts or terms.
+#[derive(Clone, Debug)]
+pub struct MakeBuilder {
+    maker: Pubkey,
+    seed: u64,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    token_program: Pubkey,
+    deposit: u64,
+    receive: u64,
+    lock_period: u64,
+    rent_payer: Option<Pubkey>,
+    proceeds_recipient: Option<Pubkey>,
+    collection: Option<(Pubkey, Option<Pubkey>)>,
+    expiry: u64,
+    bounty: u64,
+    max_referrer_fee_bps: u16,
+    list_on_market: bool,
+    evict: Option<Pubkey>,
+    position: bool,
+    reference: Option<EscrowReference>,
+    recovery: Option<RecoveryConfig>,
+}
+
+impl MakeBuilder {
+    /// `token_program` owns both mints, see `token_program_for_mint`.
+    pub fn new(maker: Pubkey, seed: u64, mint_a: Pubkey, mint_b: Pubkey, token_program: Pubkey) -> Self {
+        Self {
+            maker,
+            seed,
+            mint_a,
+            mint_b,
+            token_program,
+            deposit: 0,
+            receive: 0,
+            lock_period: 0,
+            rent_payer: None,
+            proceeds_recipient: None,
+            collection: None,
+            expiry: 0,
+            bounty: 0,
+            max_referrer_fee_bps: 0,
+            list_on_market: false,
+            evict: None,
+            position: false,
+            reference: None,
+            recovery: None,
+        }
+    }
+
+    pub fn deposit(mut self, deposit: u64) -> Self {
+        self.deposit = deposit;
+        self
+    }
+
+    pub fn receive(mut self, receive: u64) -> Self {
+        self.receive = receive;
+        self
+    }
+
+    pub fn lock_period(mut self, lock_period: u64) -> Self {
+        self.lock_period = lock_period;
+        self
+    }
+
+    /// Sponsors the escrow and vault rent. Defaults to the maker.
+    pub fn rent_payer(mut self, rent_payer: Pubkey) -> Self {
+        self.rent_payer = Some(rent_payer);
+        self
+    }
+
+    pub fn proceeds_recipient(mut self, proceeds_recipient: Pubkey) -> Self {
+        self.proceeds_recipient = Some(proceeds_recipient);
+        self
+    }
+
+    /// Requires mint_a to be an NFT of `collection`. SPL Token NFTs also
+    /// need their Metaplex metadata account.
+    pub fn collection(mut self, collection: Pubkey, metadata: Option<Pubkey>) -> Self {
+        self.collection = Some((collection, metadata));
+        self
+    }
+
+    pub fn expiry(mut self, expiry: u64, bounty: u64) -> Self {
+        self.expiry = expiry;
+        self.bounty = bounty;
+        self
+    }
+
+    pub fn max_referrer_fee_bps(mut self, max_referrer_fee_bps: u16) -> Self {
+        self.max_referrer_fee_bps = max_referrer_fee_bps;
+        self
+    }
+
+    /// Lists the escrow in its mint pair's market.
+    pub fn list_on_market(mut self) -> Self {
+        self.list_on_market = true;
+        self
+    }
+
+    /// Passes the escrow of a full market's worst order, which a better
+    /// priced listing evicts. See `Market::orders`.
+    pub fn evict(mut self, escrow: Pubkey) -> Self {
+        self.evict = Some(escrow);
+        self
+    }
+
+    /// Mints the maker a transferable position token for the escrow.
+    pub fn position(mut self) -> Self {
+        self.position = true;
+        self
+    }
+
+    pub fn reference(mut self, reference: EscrowReference) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    pub fn recovery(mut self, recovery: RecoveryConfig) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        escrow_address(&self.maker, self.seed)
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let escrow = self.escrow();
+        let position_mint = self.position.then(|| position_mint_address(&escrow));
+
+        let mut accounts = anchor_escrow::accounts::Make {
+            maker: self.maker,
+            rent_payer: self.rent_payer,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            maker_ata_a: associated_token_address(&self.maker, &self.mint_a, &self.token_program),
+            mint_a_metadata: self.collection.and_then(|(_, metadata)| metadata),
+            proceeds_recipient: self.proceeds_recipient,
+            escrow,
+            vault: vault_address(&escrow, &self.mint_a, &self.token_program),
+            market: self.list_on_market.then(|| market_address(&self.mint_a, &self.mint_b)),
+            position_mint,
+            maker_position: position_mint.map(|mint| associated_token_address(&self.maker, &mint, &self.token_program)),
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }.to_account_metas(None);
+        accounts.extend(self.evict.map(|escrow| AccountMeta::new(escrow, false)));
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: anchor_escrow::instruction::Make {
+                seed: self.seed,
+                deposit: self.deposit,
+                receive: self.receive,
+                lock_period: self.lock_period,
+                collection: self.collection.map(|(collection, _)| collection),
+                expiry: self.expiry,
+                bounty: self.bounty,
+                max_referrer_fee_bps: self.max_referrer_fee_bps,
+                reference: self.reference.clone(),
+                recovery: self.recovery,
+            }.data(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::PROGRAM_ID;
+
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &PROGRAM_ID).0
+}
+
+/// The escrow's mint_a ATA.
+pub fn vault_address(escrow: &Pubkey, mint_a: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(escrow, mint_a, token_program)
+}
+
+pub fn market_address(mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"market", mint_a.as_ref(), mint_b.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn auction_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"auction", escrow.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn position_mint_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", escrow.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn reservation_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reservation", escrow.as_ref()], &PROGRAM_ID).0
+}
+
+/// The taker's pending `commit_take` on the escrow.
+pub fn commitment_address(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"commitment", escrow.as_ref(), taker.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
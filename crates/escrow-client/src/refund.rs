@@ -0,0 +1,51 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData, ToAccountMetas};
+
+use crate::pda::{associated_token_address, market_address, vault_address};
+use crate::{Escrow, PROGRAM_ID};
+
+/// Builds a `refund` by the maker, or by the position holder for escrows
+/// with a position token.
+#[derive(Clone, Debug)]
+pub struct RefundBuilder {
+    authority: Pubkey,
+    address: Pubkey,
+    escrow: Escrow,
+    token_program: Pubkey,
+}
+
+impl RefundBuilder {
+    /// `token_program` owns the escrow's mints, see `token_program_for_mint`.
+    pub fn new(authority: Pubkey, address: Pubkey, escrow: &Escrow, token_program: Pubkey) -> Self {
+        Self {
+            authority,
+            address,
+            escrow: *escrow,
+            token_program,
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let escrow = &self.escrow;
+        let position_mint = escrow.has_position().then_some(escrow.position_mint);
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: anchor_escrow::accounts::Refund {
+                authority: self.authority,
+                maker: escrow.maker,
+                rent_recipient: escrow.rent_payer,
+                mint_a: escrow.mint_a,
+                authority_ata_a: associated_token_address(&self.authority, &escrow.mint_a, &self.token_program),
+                escrow: self.address,
+                vault: vault_address(&self.address, &escrow.mint_a, &self.token_program),
+                market: escrow.is_listed().then(|| market_address(&escrow.mint_a, &escrow.mint_b)),
+                position_mint,
+                position: position_mint.map(|mint| associated_token_address(&self.authority, &mint, &self.token_program)),
+                associated_token_program: anchor_spl::associated_token::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::Refund {}.data(),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::{AccountMeta, Instruction}, system_program, InstructionData, ToAccountMetas};
+use anchor_escrow::{instructions::AllowlistProof, utils::take_commitment};
+
+use crate::error::{ClientError, Result};
+use crate::pda::{associated_token_address, commitment_address, market_address, reservation_address, vault_address};
+use crate::{Escrow, PROGRAM_ID};
+
+/// Builds a fill of a one-shot escrow from its decoded state: the market,
+/// reservation, gate account and reference keys are added when the escrow
+/// needs them.
+///
+/// The instruction follows the options set: `reveal` sends `reveal_take`,
+/// otherwise `allowlist` sends `take_allowlisted`, `guard` sends
+/// `take_with_guard`, `referrer` sends `take_referred`, and plain `take`
+/// is sent when none are set. Only `take_referred` pays a referrer, so
+/// `referrer` cannot be combined with the others.
+#[derive(Clone, Debug)]
+pub struct TakeBuilder {
+    taker: Pubkey,
+    address: Pubkey,
+    escrow: Escrow,
+    token_program: Pubkey,
+    rent_payer: Option<Pubkey>,
+    proceeds_token_account: bool,
+    position_holder: Option<Pubkey>,
+    gate_account: Option<Pubkey>,
+    referrer: Option<(Pubkey, u16)>,
+    guard: Option<(u64, u64)>,
+    allowlist: Option<AllowlistProof>,
+    salt: Option<[u8; 32]>,
+}
+
+impl TakeBuilder {
+    /// `token_program` owns the escrow's mints, see `token_program_for_mint`.
+    pub fn new(taker: Pubkey, address: Pubkey, escrow: &Escrow, token_program: Pubkey) -> Self {
+        Self {
+            taker,
+            address,
+            escrow: *escrow,
+            token_program,
+            rent_payer: None,
+            proceeds_token_account: false,
+            position_holder: None,
+            gate_account: None,
+            referrer: None,
+            guard: None,
+            allowlist: None,
+            salt: None,
+        }
+    }
+
+    /// Sponsors any token accounts the take creates. Defaults to the taker.
+    pub fn rent_payer(mut self, rent_payer: Pubkey) -> Self {
+        self.rent_payer = Some(rent_payer);
+        self
+    }
+
+    /// The escrow's `proceeds_recipient` is a mint_b token account rather
+    /// than a wallet, so it is paid directly.
+    pub fn proceeds_token_account(mut self) -> Self {
+        self.proceeds_token_account = true;
+        self
+    }
+
+    /// Current holder of the escrow's position token, who is paid instead
+    /// of the maker.
+    pub fn position_holder(mut self, holder: Pubkey) -> Self {
+        self.position_holder = Some(holder);
+        self
+    }
+
+    /// Taker's gate token account, when it is not their ATA of the gate mint.
+    pub fn gate_account(mut self, gate_account: Pubkey) -> Self {
+        self.gate_account = Some(gate_account);
+        self
+    }
+
+    pub fn referrer(mut self, referrer: Pubkey, fee_bps: u16) -> Self {
+        self.referrer = Some((referrer, fee_bps));
+        self
+    }
+
+    /// Fails the take if the price rose above `expected_receive`, the vault
+    /// fell below `min_amount_a`, or the terms changed since decoding.
+    pub fn guard(mut self, expected_receive: u64, min_amount_a: u64) -> Self {
+        self.guard = Some((expected_receive, min_amount_a));
+        self
+    }
+
+    pub fn allowlist(mut self, proof: AllowlistProof) -> Self {
+        self.allowlist = Some(proof);
+        self
+    }
+
+    /// Reveals a `commit_take` made with `commitment(salt)`.
+    pub fn reveal(mut self, salt: [u8; 32]) -> Self {
+        self.salt = Some(salt);
+        self
+    }
+
+    /// Hash to pass to `commit_take` before revealing `salt`.
+    pub fn commitment(&self, salt: &[u8; 32]) -> [u8; 32] {
+        take_commitment(&self.taker, &self.address, salt)
+    }
+
+    /// The `commit_take` to send at least `REVEAL_DELAY` slots before
+    /// revealing `salt`.
+    pub fn commit_instruction(&self, salt: &[u8; 32]) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: anchor_escrow::accounts::CommitTake {
+                taker: self.taker,
+                escrow: self.address,
+                commitment: commitment_address(&self.address, &self.taker),
+                system_program: system_program::ID,
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::CommitTake { commitment: self.commitment(salt) }.data(),
+        }
+    }
+
+    pub fn instruction(&self) -> Result<Instruction> {
+        let escrow = &self.escrow;
+
+        let (proceeds_recipient, position) = if escrow.has_position() {
+            let holder = self.position_holder.ok_or(ClientError::MissingPositionHolder)?;
+            (holder, Some(associated_token_address(&holder, &escrow.position_mint, &self.token_program)))
+        } else {
+            (escrow.proceeds_recipient, None)
+        };
+        let maker_ata_b = (!self.proceeds_token_account)
+            .then(|| associated_token_address(&proceeds_recipient, &escrow.mint_b, &self.token_program));
+        let reserved = escrow.is_reserved() && escrow.reserved_by == self.taker;
+        let reservation = reserved.then(|| reservation_address(&self.address));
+
+        let mut accounts = anchor_escrow::accounts::Take {
+            taker: self.taker,
+            maker: escrow.maker,
+            rent_payer: self.rent_payer,
+            rent_recipient: escrow.rent_payer,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            taker_ata_a: associated_token_address(&self.taker, &escrow.mint_a, &self.token_program),
+            taker_ata_b: associated_token_address(&self.taker, &escrow.mint_b, &self.token_program),
+            proceeds_recipient,
+            maker_ata_b,
+            referrer: self.referrer.map(|(referrer, _)| referrer),
+            referrer_ata_b: self.referrer.map(|(referrer, _)| associated_token_address(&referrer, &escrow.mint_b, &self.token_program)),
+            escrow: self.address,
+            vault: vault_address(&self.address, &escrow.mint_a, &self.token_program),
+            market: escrow.is_listed().then(|| market_address(&escrow.mint_a, &escrow.mint_b)),
+            position,
+            reservation,
+            reservation_vault: reservation.map(|reservation| associated_token_address(&reservation, &escrow.mint_b, &self.token_program)),
+            commitment: self.salt.map(|_| commitment_address(&self.address, &self.taker)),
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        }.to_account_metas(None);
+
+        // The gate account comes first, then the reference keys
+        if escrow.is_gated() {
+            let gate_account = self
+                .gate_account
+                .unwrap_or_else(|| associated_token_address(&self.taker, &escrow.gate_mint, &self.token_program));
+            accounts.push(AccountMeta::new_readonly(gate_account, false));
+        }
+        accounts.extend(
+            escrow
+                .references
+                .iter()
+                .filter(|reference| **reference != Pubkey::default())
+                .map(|reference| AccountMeta::new_readonly(*reference, false)),
+        );
+
+        Ok(Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: self.data()?,
+        })
+    }
+
+    fn data(&self) -> Result<Vec<u8>> {
+        if let Some(salt) = self.salt {
+            self.check_no_referrer("reveal_take")?;
+            return Ok(anchor_escrow::instruction::RevealTake { salt, allowlist: self.allowlist.clone() }.data());
+        }
+        if let Some(allowlist) = &self.allowlist {
+            self.check_no_referrer("take_allowlisted")?;
+            return Ok(anchor_escrow::instruction::TakeAllowlisted { allowlist: allowlist.clone() }.data());
+        }
+        if let Some((expected_receive, min_amount_a)) = self.guard {
+            self.check_no_referrer("take_with_guard")?;
+            return Ok(anchor_escrow::instruction::TakeWithGuard {
+                expected_receive,
+                min_amount_a,
+                terms_hash: self.escrow.terms_hash()?,
+            }.data());
+        }
+        if let Some((_, referrer_fee_bps)) = self.referrer {
+            return Ok(anchor_escrow::instruction::TakeReferred { referrer_fee_bps }.data());
+        }
+
+        Ok(anchor_escrow::instruction::Take {}.data())
+    }
+
+    fn check_no_referrer(&self, instruction: &'static str) -> Result<()> {
+        match self.referrer {
+            Some(_) => Err(ClientError::ReferrerNotSupported(instruction)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::AccountMeta, Discriminator};
+use anchor_escrow::state::{Market, Pricing};
+use bytemuck::Zeroable;
+
+use crate::*;
+
+fn escrow(maker: Pubkey, mint_a: Pubkey, mint_b: Pubkey) -> Escrow {
+    let mut escrow = Escrow::zeroed();
+    escrow.seed = 7;
+    escrow.maker = maker;
+    escrow.mint_a = mint_a;
+    escrow.mint_b = mint_b;
+    escrow.receive = 2_500_000;
+    escrow.rent_payer = maker;
+    escrow.proceeds_recipient = maker;
+    escrow.set_pricing(Pricing::Fixed);
+    escrow
+}
+
+fn account_data(escrow: &Escrow) -> Vec<u8> {
+    [Escrow::DISCRIMINATOR, bytemuck::bytes_of(escrow)].concat()
+}
+
+#[test]
+fn test_format_amount() {
+    assert_eq!(format_amount(1_500_000, 6), "1.5");
+    assert_eq!(format_amount(2_000_000, 6), "2");
+    assert_eq!(format_amount(1, 9), "0.000000001");
+    assert_eq!(format_amount(42, 0), "42");
+    assert_eq!(format_amount(u64::MAX, 19), "1.8446744073709551615");
+}
+
+#[test]
+fn test_token_program_for_mint() {
+    let mint = Pubkey::new_unique();
+    assert_eq!(token_program_for_mint(&mint, &anchor_spl::token::ID).unwrap(), anchor_spl::token::ID);
+    assert_eq!(token_program_for_mint(&mint, &anchor_spl::token_2022::ID).unwrap(), anchor_spl::token_2022::ID);
+    assert!(matches!(
+        token_program_for_mint(&mint, &Pubkey::new_unique()),
+        Err(ClientError::NotATokenAccount(key)) if key == mint
+    ));
+}
+
+#[test]
+fn test_make_builder_derives_accounts() {
+    let (maker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let token_program = anchor_spl::token_2022::ID;
+    let evicted = Pubkey::new_unique();
+
+    let ix = MakeBuilder::new(maker, 7, mint_a, mint_b, token_program)
+        .deposit(10)
+        .receive(20)
+        .list_on_market()
+        .evict(evicted)
+        .instruction();
+
+    let escrow = escrow_address(&maker, 7);
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(ix.program_id, PROGRAM_ID);
+    assert_eq!(keys[0], maker);
+    assert_eq!(keys[1], PROGRAM_ID, "Expected the rent payer to be omitted so the maker pays it");
+    assert_eq!(keys[4], associated_token_address(&maker, &mint_a, &token_program));
+    assert_eq!(keys[7], escrow);
+    assert_eq!(keys[8], vault_address(&escrow, &mint_a, &token_program));
+    assert_eq!(keys[9], market_address(&mint_a, &mint_b));
+    // Omitted optional accounts are passed as the program ID
+    assert_eq!(keys[10], PROGRAM_ID);
+    assert_eq!(keys[13], token_program);
+    assert_eq!(ix.accounts[15], AccountMeta::new(evicted, false), "Expected the evicted escrow to follow the named accounts");
+}
+
+#[test]
+fn test_take_builder_appends_gate_and_references() {
+    let (maker, taker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let address = escrow_address(&maker, 7);
+    let mut state = escrow(maker, mint_a, mint_b);
+    state.gate_mint = Pubkey::new_unique();
+    state.gate_min_balance = 1;
+    state.references[0] = Pubkey::new_unique();
+    state.reserved_by = taker;
+
+    let token_program = anchor_spl::token::ID;
+    let ix = TakeBuilder::new(taker, address, &state, token_program).instruction().unwrap();
+
+    let reservation = reservation_address(&address);
+    assert_eq!(ix.data, anchor_lang::InstructionData::data(&anchor_escrow::instruction::Take {}));
+    assert_eq!(ix.accounts.len(), 24);
+    assert_eq!(ix.accounts[9].pubkey, associated_token_address(&maker, &mint_b, &token_program));
+    assert_eq!(ix.accounts[16].pubkey, reservation);
+    assert_eq!(ix.accounts[17].pubkey, associated_token_address(&reservation, &mint_b, &token_program));
+    assert_eq!(ix.accounts[18].pubkey, PROGRAM_ID, "Expected no commitment outside reveal_take");
+    assert_eq!(
+        ix.accounts[22..],
+        [
+            AccountMeta::new_readonly(associated_token_address(&taker, &state.gate_mint, &token_program), false),
+            AccountMeta::new_readonly(state.references[0], false),
+        ]
+    );
+
+    state.position_mint = Pubkey::new_unique();
+    assert!(matches!(
+        TakeBuilder::new(taker, address, &state, token_program).instruction(),
+        Err(ClientError::MissingPositionHolder)
+    ));
+}
+
+#[test]
+fn test_take_builder_rejects_referrer_without_take_referred() {
+    let (maker, taker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let address = escrow_address(&maker, 7);
+    let builder = TakeBuilder::new(taker, address, &escrow(maker, mint_a, mint_b), anchor_spl::token::ID).referrer(Pubkey::new_unique(), 50);
+
+    let ix = builder.clone().instruction().unwrap();
+    assert_eq!(ix.data, anchor_lang::InstructionData::data(&anchor_escrow::instruction::TakeReferred { referrer_fee_bps: 50 }));
+
+    assert!(matches!(
+        builder.clone().guard(2_500_000, 1).instruction(),
+        Err(ClientError::ReferrerNotSupported("take_with_guard"))
+    ));
+    assert!(matches!(
+        builder.reveal([1; 32]).instruction(),
+        Err(ClientError::ReferrerNotSupported("reveal_take"))
+    ));
+}
+
+#[test]
+fn test_take_builder_reveals_own_commitment() {
+    let (maker, taker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let address = escrow_address(&maker, 7);
+    let builder = TakeBuilder::new(taker, address, &escrow(maker, mint_a, mint_b), anchor_spl::token::ID);
+    let salt = [3; 32];
+
+    // Each taker commits to their own account, which the reveal then closes
+    let commitment = commitment_address(&address, &taker);
+    assert_ne!(commitment, commitment_address(&address, &maker));
+
+    let commit_ix = builder.commit_instruction(&salt);
+    assert_eq!(commit_ix.accounts[2], AccountMeta::new(commitment, false));
+    assert_eq!(
+        commit_ix.data,
+        anchor_lang::InstructionData::data(&anchor_escrow::instruction::CommitTake { commitment: builder.commitment(&salt) })
+    );
+
+    let reveal_ix = builder.reveal(salt).instruction().unwrap();
+    assert_eq!(reveal_ix.accounts[18], AccountMeta::new(commitment, false));
+}
+
+#[test]
+fn test_decode_escrow_summary() {
+    let (maker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let address = escrow_address(&maker, 7);
+    let state = escrow(maker, mint_a, mint_b);
+
+    let decoded = decode_escrow(&account_data(&state)).unwrap();
+    assert_eq!(decoded.maker, maker);
+    assert_eq!(decoded.receive, 2_500_000);
+    assert!(decode_escrow(&[0; 8]).is_err());
+
+    // Truncated, oversized, foreign or misaligned data never panics
+    let data = account_data(&state);
+    for malformed in [&data[..data.len() - 1], &[data.as_slice(), &[0]].concat(), &[&[0; 8], &data[8..]].concat(), &data[..4]] {
+        assert!(matches!(decode_escrow(malformed), Err(ClientError::Decode { account: "Escrow", .. })));
+    }
+    let misaligned = [&[0], data.as_slice()].concat();
+    assert_eq!(decode_escrow(&misaligned[1..]).unwrap().maker, maker);
+
+    let market = Market { mint_a, mint_b, orders: vec![], bump: 1 };
+    let market_data = [Market::DISCRIMINATOR, &anchor_lang::AnchorSerialize::try_to_vec(&market).unwrap(), &[0; 64]].concat();
+    assert_eq!(decode_market(&market_data).unwrap().bump, 1);
+    assert!(matches!(decode_market(&data), Err(ClientError::Decode { account: "Market", .. })));
+    assert!(matches!(decode_market(&market_data[..10]), Err(ClientError::Decode { account: "Market", .. })));
+
+    let summary = EscrowSummary::new(address, &decoded, 1_000_000_000, 9, 6);
+    assert_eq!(summary.amount_a, "1");
+    assert_eq!(summary.receive, "2.5");
+    assert_eq!(summary.pricing, "fixed");
+    assert!(summary.to_string().contains(&format!("for     2.5 of {}", mint_b)));
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::error::{ClientError, Result};
+
+/// Token program a mint belongs to, from the owner of its account.
+pub fn token_program_for_mint(mint: &Pubkey, owner: &Pubkey) -> Result<Pubkey> {
+    if *owner == anchor_spl::token::ID || *owner == anchor_spl::token_2022::ID {
+        Ok(*owner)
+    } else {
+        Err(ClientError::NotATokenAccount(*mint))
+    }
+}
+
+/// Renders a raw token amount with the mint's decimals, trimming trailing
+/// zeros: `1_500_000` with 6 decimals is `"1.5"`.
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let scale = 10u128.pow(decimals as u32);
+    let whole = amount as u128 / scale;
+    let fraction = amount as u128 % scale;
+
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
@@ -0,0 +1,154 @@
+use anchor_escrow::{events::TakeEvent, instruction};
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::transaction::IndexedInstruction;
+
+// Account positions shared by every instruction that uses the `Make`
+// context, and by `refund`, `close_stale` and `recover`, whose first
+// account is the one closing the escrow.
+const MAKE_MAKER: usize = 0;
+const MAKE_MINT_A: usize = 2;
+const MAKE_MINT_B: usize = 3;
+const MAKE_ESCROW: usize = 7;
+const CLOSE_AUTHORITY: usize = 0;
+const CLOSE_MAKER: usize = 1;
+const CLOSE_ESCROW: usize = 5;
+// Positions in `settle`.
+const SETTLE_WINNER: usize = 3;
+const SETTLE_ESCROW: usize = 9;
+
+/// An escrow program instruction the indexer records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EscrowInstruction {
+    Make {
+        escrow: Pubkey,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        seed: u64,
+        deposit: u64,
+    },
+    Refund {
+        escrow: Pubkey,
+        maker: Pubkey,
+        authority: Pubkey,
+        kind: RefundKind,
+    },
+    /// An English auction settled, sending the vault to `winner`: the
+    /// highest bidder, or the maker when nobody bid.
+    Settle {
+        escrow: Pubkey,
+        winner: Pubkey,
+    },
+}
+
+/// How an escrow was returned to its maker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefundKind {
+    /// By the maker or position holder.
+    Refund,
+    /// By a cranker after expiry.
+    CloseStale,
+    /// By the recovery authority.
+    Recover,
+}
+
+impl RefundKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefundKind::Refund => "refund",
+            RefundKind::CloseStale => "close_stale",
+            RefundKind::Recover => "recover",
+        }
+    }
+}
+
+/// Decodes `ix` if it is an escrow program make, refund or settle. Takes are read
+/// from [`take_events`] instead, which also covers `take_many` and
+/// standing orders.
+pub fn decode_instruction(ix: &IndexedInstruction) -> Option<EscrowInstruction> {
+    if ix.program_id != escrow_client::PROGRAM_ID || ix.data.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut args) = ix.data.split_at(8);
+    let account = |index: usize| ix.accounts.get(index).copied();
+
+    let make = |seed: u64, deposit: u64| {
+        Some(EscrowInstruction::Make {
+            escrow: account(MAKE_ESCROW)?,
+            maker: account(MAKE_MAKER)?,
+            mint_a: account(MAKE_MINT_A)?,
+            mint_b: account(MAKE_MINT_B)?,
+            seed,
+            deposit,
+        })
+    };
+    let refund = |kind: RefundKind| {
+        Some(EscrowInstruction::Refund {
+            escrow: account(CLOSE_ESCROW)?,
+            maker: account(CLOSE_MAKER)?,
+            authority: account(CLOSE_AUTHORITY)?,
+            kind,
+        })
+    };
+
+    match discriminator {
+        instruction::Make::DISCRIMINATOR => {
+            let args = instruction::Make::deserialize(&mut args).ok()?;
+            make(args.seed, args.deposit)
+        }
+        instruction::MakeDutch::DISCRIMINATOR => {
+            let args = instruction::MakeDutch::deserialize(&mut args).ok()?;
+            make(args.seed, args.deposit)
+        }
+        instruction::MakeStanding::DISCRIMINATOR => {
+            let args = instruction::MakeStanding::deserialize(&mut args).ok()?;
+            make(args.seed, args.deposit)
+        }
+        instruction::Refund::DISCRIMINATOR => refund(RefundKind::Refund),
+        instruction::CloseStale::DISCRIMINATOR => refund(RefundKind::CloseStale),
+        instruction::Recover::DISCRIMINATOR => refund(RefundKind::Recover),
+        instruction::Settle::DISCRIMINATOR => Some(EscrowInstruction::Settle {
+            escrow: account(SETTLE_ESCROW)?,
+            winner: account(SETTLE_WINNER)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Every `TakeEvent` the escrow program logged, in order. Data logged by
+/// other programs is skipped by following the invoke stack.
+pub fn take_events(logs: &[String]) -> Vec<TakeEvent> {
+    let program_id = escrow_client::PROGRAM_ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let Ok(bytes) = STANDARD.decode(data) else {
+                continue;
+            };
+            if let Some(mut payload) = bytes.strip_prefix(TakeEvent::DISCRIMINATOR) {
+                if let Ok(event) = TakeEvent::deserialize(&mut payload) {
+                    events.push(event);
+                }
+            }
+        } else if let Some((program, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                stack.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+
+    events
+}
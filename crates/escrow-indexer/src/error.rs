@@ -0,0 +1,13 @@
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("invalid transaction JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid transaction {signature}: {reason}")]
+    InvalidTransaction { signature: String, reason: String },
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
@@ -0,0 +1,20 @@
+//! Indexes `anchor_escrow` makes, fills, refunds and auction settlements
+//! into SQLite.
+//!
+//! Transactions come in as [`IndexedTransaction`]s, built from LiteSVM
+//! results or parsed from `getTransaction` JSON by
+//! [`parse_rpc_transaction`], and are queried back through [`Indexer`].
+
+pub mod decode;
+pub mod error;
+pub mod rpc;
+pub mod store;
+pub mod transaction;
+#[cfg(test)]
+mod tests;
+
+pub use decode::*;
+pub use error::*;
+pub use rpc::*;
+pub use store::*;
+pub use transaction::*;
@@ -0,0 +1,157 @@
+//! Reads transactions in the shape `getTransaction` returns with `json`
+//! encoding, so a node, a recorded fixture or any stand-in serving that
+//! shape can feed the indexer.
+//!
+//! `getTransaction` does not return account data, so the stand-in adds a
+//! `postAccounts` list in the shape `getMultipleAccounts` returns with
+//! `base64` encoding.
+
+use anchor_lang::prelude::Pubkey;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+
+use crate::error::{IndexerError, Result};
+use crate::transaction::{IndexedAccount, IndexedInstruction, IndexedTransaction};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    slot: u64,
+    block_time: Option<i64>,
+    transaction: RpcTransactionBody,
+    meta: RpcMeta,
+    #[serde(default)]
+    post_accounts: Vec<RpcAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcTransactionBody {
+    signatures: Vec<String>,
+    message: RpcMessage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMessage {
+    account_keys: Vec<String>,
+    instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcInstruction {
+    program_id_index: usize,
+    accounts: Vec<usize>,
+    /// Base58, as the `json` encoding returns it.
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMeta {
+    err: Option<serde_json::Value>,
+    #[serde(default)]
+    log_messages: Option<Vec<String>>,
+    #[serde(default)]
+    inner_instructions: Option<Vec<RpcInnerInstructions>>,
+    #[serde(default)]
+    loaded_addresses: Option<RpcLoadedAddresses>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcInnerInstructions {
+    index: usize,
+    instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RpcLoadedAddresses {
+    writable: Vec<String>,
+    readonly: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcAccount {
+    pubkey: String,
+    owner: String,
+    /// `[data, "base64"]`.
+    data: (String, String),
+}
+
+/// Parses one `getTransaction` result, with the stand-in's
+/// `postAccounts`.
+pub fn parse_rpc_transaction(json: &str) -> Result<IndexedTransaction> {
+    let rpc: RpcTransaction = serde_json::from_str(json)?;
+
+    let signature = rpc.transaction.signatures.first().cloned().ok_or_else(|| IndexerError::InvalidTransaction {
+        signature: String::new(),
+        reason: "transaction has no signatures".to_string(),
+    })?;
+    let invalid = |reason: String| IndexerError::InvalidTransaction {
+        signature: signature.clone(),
+        reason,
+    };
+    let parse_key = |key: &String| key.parse::<Pubkey>().map_err(|_| invalid(format!("invalid address {}", key)));
+
+    // Keys loaded from lookup tables follow the static keys, writable first
+    let loaded = rpc.meta.loaded_addresses.unwrap_or_default();
+    let keys = rpc
+        .transaction
+        .message
+        .account_keys
+        .iter()
+        .chain(&loaded.writable)
+        .chain(&loaded.readonly)
+        .map(parse_key)
+        .collect::<Result<Vec<_>>>()?;
+
+    let instruction = |ix: &RpcInstruction| -> Result<IndexedInstruction> {
+        let key = |index: usize| keys.get(index).copied().ok_or_else(|| invalid(format!("account index {} out of range", index)));
+        Ok(IndexedInstruction {
+            program_id: key(ix.program_id_index)?,
+            accounts: ix.accounts.iter().map(|index| key(*index)).collect::<Result<_>>()?,
+            data: bs58::decode(&ix.data)
+                .into_vec()
+                .map_err(|err| invalid(format!("invalid instruction data: {}", err)))?,
+        })
+    };
+
+    let inner = rpc.meta.inner_instructions.unwrap_or_default();
+    let mut instructions = Vec::new();
+    for (index, ix) in rpc.transaction.message.instructions.iter().enumerate() {
+        instructions.push(instruction(ix)?);
+        for invoked in inner.iter().filter(|inner| inner.index == index) {
+            for ix in &invoked.instructions {
+                instructions.push(instruction(ix)?);
+            }
+        }
+    }
+
+    let accounts = rpc
+        .post_accounts
+        .iter()
+        .map(|account| {
+            let (data, encoding) = &account.data;
+            if encoding != "base64" {
+                return Err(invalid(format!("unsupported account encoding {}", encoding)));
+            }
+            Ok(IndexedAccount {
+                address: parse_key(&account.pubkey)?,
+                owner: parse_key(&account.owner)?,
+                data: STANDARD
+                    .decode(data)
+                    .map_err(|err| invalid(format!("invalid account data: {}", err)))?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(IndexedTransaction {
+        slot: rpc.slot,
+        block_time: rpc.block_time,
+        failed: rpc.meta.err.is_some(),
+        instructions,
+        logs: rpc.meta.log_messages.unwrap_or_default(),
+        accounts,
+        signature,
+    })
+}
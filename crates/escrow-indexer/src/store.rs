@@ -0,0 +1,352 @@
+use std::{collections::HashSet, path::Path};
+
+use anchor_lang::prelude::Pubkey;
+use escrow_client::{decode_escrow, Escrow};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+use crate::decode::{decode_instruction, take_events, EscrowInstruction};
+use crate::error::Result;
+use crate::transaction::IndexedTransaction;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    failed INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS escrows (
+    address TEXT PRIMARY KEY,
+    maker TEXT NOT NULL,
+    mint_a TEXT NOT NULL,
+    mint_b TEXT NOT NULL,
+    -- u64 amounts are stored as decimal TEXT, as they can exceed INTEGER
+    seed TEXT NOT NULL,
+    -- mint_a escrowed by the make, NULL when the make was not indexed
+    deposit TEXT,
+    receive TEXT,
+    -- mint_a a standing order still offers as of updated_slot, NULL for
+    -- one-shot escrows
+    remaining TEXT,
+    -- open, filled, refunded or settled
+    status TEXT NOT NULL,
+    created_slot INTEGER,
+    created_signature TEXT,
+    closed_slot INTEGER,
+    closed_signature TEXT,
+    updated_slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS escrows_by_pair ON escrows (mint_a, mint_b, status);
+CREATE INDEX IF NOT EXISTS escrows_by_maker ON escrows (maker);
+
+CREATE TABLE IF NOT EXISTS fills (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    escrow TEXT NOT NULL,
+    maker TEXT NOT NULL,
+    taker TEXT NOT NULL,
+    amount_a TEXT NOT NULL,
+    amount_b TEXT NOT NULL,
+    referrer TEXT,
+    referrer_fee TEXT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS fills_by_maker ON fills (maker, slot);
+CREATE INDEX IF NOT EXISTS fills_by_escrow ON fills (escrow);
+
+CREATE TABLE IF NOT EXISTS refunds (
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    escrow TEXT NOT NULL,
+    maker TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    -- refund, close_stale or recover
+    kind TEXT NOT NULL,
+    PRIMARY KEY (signature, escrow)
+);
+CREATE INDEX IF NOT EXISTS refunds_by_maker ON refunds (maker, slot);
+";
+
+/// Escrow lifecycle stored in SQLite.
+///
+/// Makes and refunds are read from instructions, fills from `TakeEvent`
+/// logs, and current terms from the escrow accounts a transaction leaves
+/// open. An escrow with a fill is marked filled unless the transaction's
+/// post-state still holds it, which is how standing orders stay open.
+/// Auction settlements log no fill; the `settle` instruction marks the
+/// escrow settled.
+pub struct Indexer {
+    conn: Connection,
+}
+
+/// An open escrow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Offer {
+    pub address: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub seed: u64,
+    /// mint_a escrowed by the make. Standing order fills do not change it,
+    /// see `remaining`.
+    pub deposit: Option<u64>,
+    pub receive: Option<u64>,
+    /// mint_a a standing order still offers, refreshed from each indexed
+    /// transaction's post-state. `None` for one-shot escrows.
+    pub remaining: Option<u64>,
+    pub created_slot: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fill {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub referrer: Option<Pubkey>,
+    pub referrer_fee: u64,
+}
+
+impl Indexer {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// The underlying connection, for queries not covered here.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Records `tx` atomically. Returns `false` if it was already indexed.
+    pub fn index(&mut self, tx: &IndexedTransaction) -> Result<bool> {
+        let db = self.conn.transaction()?;
+
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time, failed) VALUES (?1, ?2, ?3, ?4)",
+            params![tx.signature, tx.slot, tx.block_time, tx.failed],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        if !tx.failed {
+            index_transaction(&db, tx)?;
+        }
+
+        db.commit()?;
+        Ok(true)
+    }
+
+    /// Highest slot indexed, to resume from.
+    pub fn last_slot(&self) -> Result<Option<u64>> {
+        Ok(self.conn.query_row("SELECT MAX(slot) FROM transactions", [], |row| row.get(0))?)
+    }
+
+    /// Open escrows selling `mint_a` for `mint_b`, oldest first.
+    pub fn open_offers(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Vec<Offer>> {
+        let mut statement = self.conn.prepare(
+            "SELECT address, maker, mint_a, mint_b, seed, deposit, receive, remaining, created_slot FROM escrows
+             WHERE mint_a = ?1 AND mint_b = ?2 AND status = 'open'
+             ORDER BY created_slot, address",
+        )?;
+        let offers = statement
+            .query_map(params![mint_a.to_string(), mint_b.to_string()], offer_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(offers)
+    }
+
+    /// Every fill of escrows made by `maker`, oldest first.
+    pub fn fills_by_maker(&self, maker: &Pubkey) -> Result<Vec<Fill>> {
+        let mut statement = self.conn.prepare(
+            "SELECT signature, slot, block_time, escrow, maker, taker, amount_a, amount_b, referrer, referrer_fee FROM fills
+             WHERE maker = ?1
+             ORDER BY slot, signature, event_index",
+        )?;
+        let fills = statement
+            .query_map(params![maker.to_string()], fill_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(fills)
+    }
+
+    /// `open`, `filled`, `refunded` or `settled`, if the escrow has been seen.
+    pub fn escrow_status(&self, escrow: &Pubkey) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT status FROM escrows WHERE address = ?1", params![escrow.to_string()], |row| row.get(0))
+            .optional()?)
+    }
+}
+
+fn index_transaction(db: &Transaction, tx: &IndexedTransaction) -> Result<()> {
+    for ix in &tx.instructions {
+        match decode_instruction(ix) {
+            Some(EscrowInstruction::Make { escrow, maker, mint_a, mint_b, seed, deposit }) => {
+                db.execute(
+                    "INSERT INTO escrows (address, maker, mint_a, mint_b, seed, deposit, status, created_slot, created_signature, updated_slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'open', ?7, ?8, ?7)
+                     ON CONFLICT (address) DO UPDATE SET
+                        maker = excluded.maker, mint_a = excluded.mint_a, mint_b = excluded.mint_b, seed = excluded.seed,
+                        deposit = excluded.deposit, status = 'open', created_slot = excluded.created_slot,
+                        created_signature = excluded.created_signature, closed_slot = NULL, closed_signature = NULL,
+                        updated_slot = excluded.updated_slot",
+                    params![
+                        escrow.to_string(),
+                        maker.to_string(),
+                        mint_a.to_string(),
+                        mint_b.to_string(),
+                        seed.to_string(),
+                        deposit.to_string(),
+                        tx.slot,
+                        tx.signature,
+                    ],
+                )?;
+            }
+            Some(EscrowInstruction::Refund { escrow, maker, authority, kind }) => {
+                db.execute(
+                    "INSERT OR IGNORE INTO refunds (signature, slot, block_time, escrow, maker, authority, kind)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![tx.signature, tx.slot, tx.block_time, escrow.to_string(), maker.to_string(), authority.to_string(), kind.as_str()],
+                )?;
+                close(db, tx, &escrow, "refunded")?;
+            }
+            Some(EscrowInstruction::Settle { escrow, .. }) => {
+                close(db, tx, &escrow, "settled")?;
+            }
+            None => {}
+        }
+    }
+
+    let mut filled = HashSet::new();
+    for (index, event) in take_events(&tx.logs).iter().enumerate() {
+        db.execute(
+            "INSERT OR IGNORE INTO fills (signature, event_index, slot, block_time, escrow, maker, taker, amount_a, amount_b, referrer, referrer_fee)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                tx.signature,
+                index,
+                tx.slot,
+                tx.block_time,
+                event.escrow.to_string(),
+                event.maker.to_string(),
+                event.taker.to_string(),
+                event.amount_a.to_string(),
+                event.amount_b.to_string(),
+                event.referrer.map(|referrer| referrer.to_string()),
+                event.referrer_fee.to_string(),
+            ],
+        )?;
+        filled.insert(event.escrow);
+    }
+
+    for escrow in &filled {
+        if tx.account(escrow).is_none() {
+            close(db, tx, escrow, "filled")?;
+        }
+    }
+
+    // Refresh the terms of escrows left open, creating rows for escrows
+    // made before indexing started
+    for account in &tx.accounts {
+        if account.owner != escrow_client::PROGRAM_ID {
+            continue;
+        }
+        if let Ok(escrow) = decode_escrow(&account.data) {
+            upsert_escrow(db, tx, &account.address, &escrow)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn close(db: &Transaction, tx: &IndexedTransaction, escrow: &Pubkey, status: &str) -> Result<()> {
+    db.execute(
+        "UPDATE escrows SET status = ?2, closed_slot = ?3, closed_signature = ?4, updated_slot = ?3 WHERE address = ?1",
+        params![escrow.to_string(), status, tx.slot, tx.signature],
+    )?;
+    Ok(())
+}
+
+fn upsert_escrow(db: &Transaction, tx: &IndexedTransaction, address: &Pubkey, escrow: &Escrow) -> Result<()> {
+    db.execute(
+        "INSERT INTO escrows (address, maker, mint_a, mint_b, seed, receive, remaining, status, updated_slot)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'open', ?8)
+         ON CONFLICT (address) DO UPDATE SET
+            receive = excluded.receive, remaining = excluded.remaining, updated_slot = excluded.updated_slot",
+        params![
+            address.to_string(),
+            escrow.maker.to_string(),
+            escrow.mint_a.to_string(),
+            escrow.mint_b.to_string(),
+            escrow.seed.to_string(),
+            escrow.receive.to_string(),
+            escrow.is_standing().then(|| escrow.remaining.to_string()),
+            tx.slot,
+        ],
+    )?;
+    Ok(())
+}
+
+fn pubkey(row: &Row, index: usize) -> rusqlite::Result<Pubkey> {
+    let text: String = row.get(index)?;
+    text.parse()
+        .map_err(|_| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, format!("invalid address {}", text).into()))
+}
+
+fn amount(row: &Row, index: usize) -> rusqlite::Result<u64> {
+    let text: String = row.get(index)?;
+    text.parse()
+        .map_err(|_| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, format!("invalid amount {}", text).into()))
+}
+
+fn optional_amount(row: &Row, index: usize) -> rusqlite::Result<Option<u64>> {
+    let text: Option<String> = row.get(index)?;
+    text.map(|_| amount(row, index)).transpose()
+}
+
+fn offer_from_row(row: &Row) -> rusqlite::Result<Offer> {
+    Ok(Offer {
+        address: pubkey(row, 0)?,
+        maker: pubkey(row, 1)?,
+        mint_a: pubkey(row, 2)?,
+        mint_b: pubkey(row, 3)?,
+        seed: amount(row, 4)?,
+        deposit: optional_amount(row, 5)?,
+        receive: optional_amount(row, 6)?,
+        remaining: optional_amount(row, 7)?,
+        created_slot: row.get(8)?,
+    })
+}
+
+fn fill_from_row(row: &Row) -> rusqlite::Result<Fill> {
+    let referrer: Option<String> = row.get(8)?;
+    Ok(Fill {
+        signature: row.get(0)?,
+        slot: row.get(1)?,
+        block_time: row.get(2)?,
+        escrow: pubkey(row, 3)?,
+        maker: pubkey(row, 4)?,
+        taker: pubkey(row, 5)?,
+        amount_a: amount(row, 6)?,
+        amount_b: amount(row, 7)?,
+        referrer: referrer.map(|_| pubkey(row, 8)).transpose()?,
+        referrer_fee: amount(row, 9)?,
+    })
+}
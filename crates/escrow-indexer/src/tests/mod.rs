@@ -0,0 +1,378 @@
+use std::path::PathBuf;
+
+use anchor_escrow::{events::TakeEvent, state::Pricing};
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, Discriminator, Event, InstructionData, ToAccountMetas};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytemuck::Zeroable;
+use escrow_client::{escrow_address, Escrow, MakeBuilder, RefundBuilder, TakeBuilder, PROGRAM_ID};
+use litesvm::LiteSVM;
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::*;
+
+fn escrow_data(maker: Pubkey, seed: u64, mint_a: Pubkey, mint_b: Pubkey, receive: u64) -> Vec<u8> {
+    let mut escrow = Escrow::zeroed();
+    escrow.seed = seed;
+    escrow.maker = maker;
+    escrow.mint_a = mint_a;
+    escrow.mint_b = mint_b;
+    escrow.receive = receive;
+    escrow.rent_payer = maker;
+    escrow.proceeds_recipient = maker;
+    escrow.set_pricing(Pricing::Fixed);
+    [Escrow::DISCRIMINATOR, bytemuck::bytes_of(&escrow)].concat()
+}
+
+/// A `getTransaction` result with the stand-in's `postAccounts`.
+fn rpc_json(signature: &str, slot: u64, ixs: &[Instruction], logs: &[String], post_accounts: &[(Pubkey, Vec<u8>)], err: Value) -> String {
+    let mut keys: Vec<Pubkey> = Vec::new();
+    let mut index = |key: Pubkey| match keys.iter().position(|k| *k == key) {
+        Some(position) => position,
+        None => {
+            keys.push(key);
+            keys.len() - 1
+        }
+    };
+    let instructions: Vec<Value> = ixs
+        .iter()
+        .map(|ix| {
+            json!({
+                "programIdIndex": index(ix.program_id),
+                "accounts": ix.accounts.iter().map(|meta| index(meta.pubkey)).collect::<Vec<_>>(),
+                "data": bs58::encode(&ix.data).into_string(),
+            })
+        })
+        .collect();
+
+    json!({
+        "slot": slot,
+        "blockTime": 1_700_000_000 + slot as i64,
+        "transaction": {
+            "signatures": [signature],
+            "message": {
+                "accountKeys": keys.iter().map(|key| key.to_string()).collect::<Vec<_>>(),
+                "instructions": instructions,
+            },
+        },
+        "meta": {
+            "err": err,
+            "logMessages": logs,
+            "innerInstructions": [],
+        },
+        "postAccounts": post_accounts
+            .iter()
+            .map(|(address, data)| json!({
+                "pubkey": address.to_string(),
+                "owner": PROGRAM_ID.to_string(),
+                "data": [STANDARD.encode(data), "base64"],
+            }))
+            .collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+fn program_logs(data: &[Vec<u8>]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", PROGRAM_ID)];
+    logs.extend(data.iter().map(|data| format!("Program data: {}", STANDARD.encode(data))));
+    logs.push(format!("Program {} success", PROGRAM_ID));
+    logs
+}
+
+#[test]
+fn test_take_events_follow_invoke_stack() {
+    let event = TakeEvent {
+        escrow: Pubkey::new_unique(),
+        maker: Pubkey::new_unique(),
+        taker: Pubkey::new_unique(),
+        amount_a: 10,
+        amount_b: 20,
+        referrer: None,
+        referrer_fee: 0,
+        memo: [0; 32],
+    };
+    let other = Pubkey::new_unique();
+
+    let mut logs = program_logs(&[event.data()]);
+    // The same bytes logged by another program are not ours
+    logs.push(format!("Program {} invoke [1]", other));
+    logs.push(format!("Program data: {}", STANDARD.encode(event.data())));
+    logs.push(format!("Program {} success", other));
+
+    let events = take_events(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].escrow, event.escrow);
+    assert_eq!(events[0].amount_b, 20);
+}
+
+#[test]
+fn test_index_rpc_transactions() {
+    let (maker, taker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut indexer = Indexer::open_in_memory().unwrap();
+
+    // Two offers on the pair, one filled and one refunded
+    let mut escrows = Vec::new();
+    for (slot, seed) in [(10, 1), (11, 2)] {
+        let make = MakeBuilder::new(maker, seed, mint_a, mint_b, anchor_spl::token::ID).deposit(100).receive(250);
+        let escrow = make.escrow();
+        let json = rpc_json(
+            &format!("make-{}", seed),
+            slot,
+            &[make.instruction()],
+            &program_logs(&[]),
+            &[(escrow, escrow_data(maker, seed, mint_a, mint_b, 250))],
+            Value::Null,
+        );
+        assert!(indexer.index(&parse_rpc_transaction(&json).unwrap()).unwrap());
+        escrows.push(escrow);
+    }
+    assert_eq!(indexer.last_slot().unwrap(), Some(11));
+
+    let offers = indexer.open_offers(&mint_a, &mint_b).unwrap();
+    assert_eq!(offers.iter().map(|offer| offer.address).collect::<Vec<_>>(), escrows);
+    assert_eq!(offers[0].deposit, Some(100));
+    assert_eq!(offers[0].receive, Some(250));
+    assert_eq!(offers[0].remaining, None);
+    assert!(indexer.open_offers(&mint_b, &mint_a).unwrap().is_empty());
+
+    let state = decode_escrow_data(&escrow_data(maker, 1, mint_a, mint_b, 250));
+    let take = TakeBuilder::new(taker, escrows[0], &state, anchor_spl::token::ID).instruction().unwrap();
+    let event = TakeEvent {
+        escrow: escrows[0],
+        maker,
+        taker,
+        amount_a: 100,
+        amount_b: 250,
+        referrer: None,
+        referrer_fee: 0,
+        memo: [0; 32],
+    };
+    let take_json = rpc_json("take-1", 12, &[take], &program_logs(&[event.data()]), &[], Value::Null);
+    assert!(indexer.index(&parse_rpc_transaction(&take_json).unwrap()).unwrap());
+    assert!(!indexer.index(&parse_rpc_transaction(&take_json).unwrap()).unwrap(), "Expected a re-indexed transaction to be skipped");
+
+    // A failed refund changes nothing
+    let refund = RefundBuilder::new(maker, escrows[1], &decode_escrow_data(&escrow_data(maker, 2, mint_a, mint_b, 250)), anchor_spl::token::ID).instruction();
+    let failed = rpc_json("refund-failed", 13, std::slice::from_ref(&refund), &[], &[], json!({ "InstructionError": [0, "Custom"] }));
+    assert!(indexer.index(&parse_rpc_transaction(&failed).unwrap()).unwrap());
+    assert_eq!(indexer.escrow_status(&escrows[1]).unwrap().as_deref(), Some("open"));
+
+    let refunded = rpc_json("refund-2", 14, &[refund], &program_logs(&[]), &[], Value::Null);
+    assert!(indexer.index(&parse_rpc_transaction(&refunded).unwrap()).unwrap());
+
+    assert_eq!(indexer.escrow_status(&escrows[0]).unwrap().as_deref(), Some("filled"));
+    assert_eq!(indexer.escrow_status(&escrows[1]).unwrap().as_deref(), Some("refunded"));
+    assert!(indexer.open_offers(&mint_a, &mint_b).unwrap().is_empty());
+
+    let fills = indexer.fills_by_maker(&maker).unwrap();
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].signature, "take-1");
+    assert_eq!(fills[0].taker, taker);
+    assert_eq!((fills[0].amount_a, fills[0].amount_b), (100, 250));
+    assert_eq!(fills[0].block_time, Some(1_700_000_012));
+    assert!(indexer.fills_by_maker(&taker).unwrap().is_empty());
+
+    let refunds: (String, String) = indexer
+        .connection()
+        .query_row("SELECT kind, authority FROM refunds WHERE escrow = ?1", [escrows[1].to_string()], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!(refunds, ("refund".to_string(), maker.to_string()));
+}
+
+#[test]
+fn test_index_settle_and_large_amounts() {
+    let (maker, bidder, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut indexer = Indexer::open_in_memory().unwrap();
+
+    // Amounts above i64::MAX round-trip
+    let make = MakeBuilder::new(maker, u64::MAX, mint_a, mint_b, anchor_spl::token::ID).deposit(u64::MAX - 1).receive(u64::MAX);
+    let escrow = make.escrow();
+    let json = rpc_json(
+        "make",
+        10,
+        &[make.instruction()],
+        &program_logs(&[]),
+        &[(escrow, escrow_data(maker, u64::MAX, mint_a, mint_b, u64::MAX))],
+        Value::Null,
+    );
+    assert!(indexer.index(&parse_rpc_transaction(&json).unwrap()).unwrap());
+
+    let offers = indexer.open_offers(&mint_a, &mint_b).unwrap();
+    assert_eq!((offers[0].seed, offers[0].deposit, offers[0].receive), (u64::MAX, Some(u64::MAX - 1), Some(u64::MAX)));
+
+    let settle = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Settle {
+            payer: maker,
+            maker,
+            rent_recipient: maker,
+            winner: bidder,
+            mint_a,
+            mint_b,
+            winner_ata_a: Pubkey::new_unique(),
+            proceeds_recipient: maker,
+            maker_ata_b: Some(Pubkey::new_unique()),
+            escrow,
+            auction: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            bid_vault: Pubkey::new_unique(),
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Settle {}.data(),
+    };
+    let parsed = parse_rpc_transaction(&rpc_json("settle", 11, &[settle], &program_logs(&[]), &[], Value::Null)).unwrap();
+    assert_eq!(decode_instruction(&parsed.instructions[0]), Some(EscrowInstruction::Settle { escrow, winner: bidder }));
+
+    assert!(indexer.index(&parsed).unwrap());
+    assert_eq!(indexer.escrow_status(&escrow).unwrap().as_deref(), Some("settled"));
+    assert!(indexer.open_offers(&mint_a, &mint_b).unwrap().is_empty());
+}
+
+#[test]
+fn test_index_standing_order_remaining() {
+    let (maker, taker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut indexer = Indexer::open_in_memory().unwrap();
+
+    let make = MakeBuilder::new(maker, 1, mint_a, mint_b, anchor_spl::token::ID).deposit(10).receive(50);
+    let escrow = make.escrow();
+    let standing_data = |remaining: u64| {
+        let mut state = decode_escrow_data(&escrow_data(maker, 1, mint_a, mint_b, 50));
+        state.fill_amount = 5;
+        state.remaining = remaining;
+        [Escrow::DISCRIMINATOR, bytemuck::bytes_of(&state)].concat()
+    };
+
+    let json = rpc_json("make", 10, &[make.instruction()], &program_logs(&[]), &[(escrow, standing_data(20))], Value::Null);
+    assert!(indexer.index(&parse_rpc_transaction(&json).unwrap()).unwrap());
+    let offers = indexer.open_offers(&mint_a, &mint_b).unwrap();
+    assert_eq!((offers[0].deposit, offers[0].remaining), (Some(10), Some(20)));
+
+    // A fill leaves the order open with less left to sell, and the deposit as made
+    let event = TakeEvent {
+        escrow,
+        maker,
+        taker,
+        amount_a: 5,
+        amount_b: 50,
+        referrer: None,
+        referrer_fee: 0,
+        memo: [0; 32],
+    };
+    let json = rpc_json("fill", 11, &[], &program_logs(&[event.data()]), &[(escrow, standing_data(15))], Value::Null);
+    assert!(indexer.index(&parse_rpc_transaction(&json).unwrap()).unwrap());
+    let offers = indexer.open_offers(&mint_a, &mint_b).unwrap();
+    assert_eq!((offers[0].deposit, offers[0].remaining), (Some(10), Some(15)));
+    assert_eq!(indexer.escrow_status(&escrow).unwrap().as_deref(), Some("open"));
+}
+
+#[test]
+fn test_parse_rpc_transaction_requires_signature() {
+    let mut json: Value = serde_json::from_str(&rpc_json("unsigned", 10, &[], &[], &[], Value::Null)).unwrap();
+    json["transaction"]["signatures"] = json!([]);
+
+    assert!(matches!(
+        parse_rpc_transaction(&json.to_string()),
+        Err(IndexerError::InvalidTransaction { reason, .. }) if reason == "transaction has no signatures"
+    ));
+}
+
+fn decode_escrow_data(data: &[u8]) -> Escrow {
+    escrow_client::decode_escrow(data).unwrap()
+}
+
+/// Converts a LiteSVM result the way an RPC node would report it, with
+/// the accounts the transaction left open.
+fn litesvm_transaction(svm: &LiteSVM, tx: &Transaction, result: &litesvm::types::TransactionResult) -> IndexedTransaction {
+    let keys = &tx.message.account_keys;
+    let compiled = |program_id_index: u8, accounts: &[u8], data: &[u8]| IndexedInstruction {
+        program_id: keys[program_id_index as usize],
+        accounts: accounts.iter().map(|index| keys[*index as usize]).collect(),
+        data: data.to_vec(),
+    };
+
+    let (meta, failed) = match result {
+        Ok(meta) => (meta, false),
+        Err(failed) => (&failed.meta, true),
+    };
+
+    let mut instructions = Vec::new();
+    for (index, ix) in tx.message.instructions.iter().enumerate() {
+        instructions.push(compiled(ix.program_id_index, &ix.accounts, &ix.data));
+        for inner in meta.inner_instructions.get(index).into_iter().flatten() {
+            let ix = &inner.instruction;
+            instructions.push(compiled(ix.program_id_index, &ix.accounts, &ix.data));
+        }
+    }
+
+    IndexedTransaction {
+        signature: tx.signatures[0].to_string(),
+        slot: svm.get_sysvar::<anchor_lang::prelude::Clock>().slot,
+        block_time: None,
+        failed,
+        instructions,
+        logs: meta.logs.clone(),
+        accounts: keys
+            .iter()
+            .filter_map(|address| {
+                let account = svm.get_account(address).filter(|account| account.lamports > 0)?;
+                Some(IndexedAccount { address: *address, owner: account.owner, data: account.data })
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_index_litesvm_transactions() {
+    let mut svm = LiteSVM::new();
+    let (maker, taker) = (Keypair::new(), Keypair::new());
+    for signer in [&maker, &taker] {
+        svm.airdrop(&signer.pubkey(), 10_000_000_000).unwrap();
+    }
+    // Built by `anchor build` into the workspace target
+    let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/anchor_escrow.so");
+    let _ = svm.add_program(PROGRAM_ID, &std::fs::read(so_path).expect("Failed to read program SO file"));
+
+    let mint_a = CreateMint::new(&mut svm, &maker).decimals(6).send().unwrap();
+    let mint_b = CreateMint::new(&mut svm, &maker).decimals(6).send().unwrap();
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a).send().unwrap();
+    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, 1_000).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+    MintTo::new(&mut svm, &maker, &mint_b, &taker_ata_b, 1_000).send().unwrap();
+
+    let mut indexer = Indexer::open_in_memory().unwrap();
+    let mut send = |svm: &mut LiteSVM, ix: Instruction, signer: &Keypair| {
+        let tx = Transaction::new(&[signer], Message::new(&[ix], Some(&signer.pubkey())), svm.latest_blockhash());
+        let result = svm.send_transaction(tx.clone());
+        assert!(result.is_ok(), "{:?}", result);
+        indexer.index(&litesvm_transaction(svm, &tx, &result)).unwrap();
+    };
+
+    for seed in [1, 2] {
+        let make = MakeBuilder::new(maker.pubkey(), seed, mint_a, mint_b, anchor_spl::token::ID).deposit(100).receive(40);
+        send(&mut svm, make.instruction(), &maker);
+    }
+
+    let first = escrow_address(&maker.pubkey(), 1);
+    let state = decode_escrow_data(&svm.get_account(&first).unwrap().data);
+    send(&mut svm, TakeBuilder::new(taker.pubkey(), first, &state, anchor_spl::token::ID).instruction().unwrap(), &taker);
+
+    let second = escrow_address(&maker.pubkey(), 2);
+    let state = decode_escrow_data(&svm.get_account(&second).unwrap().data);
+    send(&mut svm, RefundBuilder::new(maker.pubkey(), second, &state, anchor_spl::token::ID).instruction(), &maker);
+
+    assert_eq!(indexer.escrow_status(&first).unwrap().as_deref(), Some("filled"));
+    assert_eq!(indexer.escrow_status(&second).unwrap().as_deref(), Some("refunded"));
+    assert!(indexer.open_offers(&mint_a, &mint_b).unwrap().is_empty());
+
+    let fills = indexer.fills_by_maker(&maker.pubkey()).unwrap();
+    assert_eq!(fills.len(), 1);
+    assert_eq!((fills[0].escrow, fills[0].taker), (first, taker.pubkey()));
+    assert_eq!((fills[0].amount_a, fills[0].amount_b), (100, 40));
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::Pubkey;
+
+/// What the indexer needs from a confirmed transaction, whichever source
+/// it came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Failed transactions are recorded as seen but change nothing.
+    pub failed: bool,
+    /// Top-level instructions, each followed by the instructions it
+    /// invoked, so makes and refunds sent through CPI are seen too.
+    pub instructions: Vec<IndexedInstruction>,
+    pub logs: Vec<String>,
+    /// Accounts as they were after the transaction. Closed accounts are
+    /// absent.
+    pub accounts: Vec<IndexedAccount>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedAccount {
+    pub address: Pubkey,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+impl IndexedTransaction {
+    /// Post-transaction data of `address` if it is still open.
+    pub fn account(&self, address: &Pubkey) -> Option<&IndexedAccount> {
+        self.accounts.iter().find(|account| account.address == *address)
+    }
+}
@@ -0,0 +1,16 @@
+pub const MAX_BPS: u16 = 10_000;
+
+/// Reference pubkeys an escrow can require on `take`.
+pub const MAX_REFERENCES: usize = 2;
+
+/// Shortest maker inactivity, in slots, before a recovery authority can act.
+pub const MIN_RECOVERY_TIMEOUT: u64 = 432_000;
+
+/// Slots a take commitment must age before it can be revealed.
+pub const REVEAL_DELAY: u64 = 2;
+
+/// Slots after the delay during which a commitment can be revealed.
+pub const REVEAL_WINDOW: u64 = 150;
+
+/// Orders a `Market` book holds at once.
+pub const MAX_MARKET_ORDERS: usize = 32;
@@ -5,7 +5,207 @@ pub enum EscrowError {
     #[msg("Escrow locked. Try again after lock period elapses")]
     EscrowLocked,
 
-    #[msg("UnknownError")]
-    UnknownError,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    #[msg("Clock sysvar is unavailable")]
+    ClockUnavailable,
+
+    #[msg("Escrow terms are invalid")]
+    InvalidTerms,
+
+    #[msg("Mint does not match the escrow")]
+    MintMismatch,
+
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[msg("Vault does not belong to the escrow")]
+    VaultMismatch,
+
+    #[msg("NFT escrows must deposit exactly one token")]
+    NftDepositMustBeOne,
+
+    #[msg("Mint is not an NFT (expected 0 decimals and a supply of 1)")]
+    NotAnNft,
+
+    #[msg("NFT metadata account is required to verify the collection")]
+    MissingNftMetadata,
+
+    #[msg("NFT metadata account is invalid")]
+    InvalidNftMetadata,
+
+    #[msg("NFT does not belong to the requested collection")]
+    CollectionMismatch,
+
+    #[msg("Auction parameters are invalid")]
+    InvalidAuctionParams,
+
+    #[msg("Escrow is being sold by auction")]
+    EscrowAuctioned,
+
+    #[msg("Only fixed-price escrows can be auctioned")]
+    NotFixedPrice,
+
+    #[msg("Auction has ended")]
+    AuctionEnded,
+
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+
+    #[msg("Bid is below the minimum bid or increment")]
+    BidTooLow,
+
+    #[msg("Previous bidder token account is required to refund the outbid bid")]
+    MissingPreviousBidder,
+
+    #[msg("A bounty requires an expiry")]
+    BountyWithoutExpiry,
+
+    #[msg("Escrow has not expired yet")]
+    EscrowNotExpired,
+
+    #[msg("Referrer fee exceeds the maker's cap")]
+    ReferrerFeeTooHigh,
+
+    #[msg("Referrer and referrer token account are required for a referrer fee")]
+    MissingReferrer,
+
+    #[msg("Escrow price is above what the taker expected")]
+    ReceiveMismatch,
+
+    #[msg("Vault holds less than the taker's minimum")]
+    VaultBelowMinimum,
+
+    #[msg("Escrow terms differ from what the taker signed")]
+    TermsHashMismatch,
+
+    #[msg("Remaining accounts do not match the expected escrow groups")]
+    InvalidRemainingAccounts,
+
+    #[msg("Proceeds recipient does not match the escrow")]
+    ProceedsRecipientMismatch,
+
+    #[msg("Maker ATA B is required when the proceeds recipient is not a token account")]
+    MissingProceedsAccount,
+
+    #[msg("Escrow is allowlisted, take it with an allowlist proof")]
+    AllowlistProofRequired,
+
+    #[msg("Taker is not in the escrow allowlist")]
+    NotAllowlisted,
+
+    #[msg("Vault holds more than the taker's allowlist cap")]
+    AllowlistCapExceeded,
+
+    #[msg("Escrow is token-gated, pass the taker's gate token account")]
+    MissingGateAccount,
+
+    #[msg("Gate token account is not the taker's account for the gate mint")]
+    InvalidGateAccount,
+
+    #[msg("Taker holds less than the gate minimum")]
+    GateBalanceTooLow,
+
+    #[msg("Escrow is a standing order, take it with take_standing")]
+    StandingOrder,
+
+    #[msg("Escrow is not a standing order")]
+    NotStandingOrder,
+
+    #[msg("Standing order has no quantity left")]
+    StandingOrderExhausted,
+
+    #[msg("Vault holds less than one fill, refill it first")]
+    VaultNeedsRefill,
+
+    #[msg("Nothing to refill from the maker's delegated token account")]
+    NothingToRefill,
+
+    #[msg("Market book is full and the order is not better than its worst")]
+    MarketFull,
+
+    #[msg("Escrow is listed, pass its market so it can be delisted")]
+    MissingMarket,
+
+    #[msg("Escrow is listed on a market")]
+    EscrowListed,
+
+    #[msg("Only fixed-price escrows without a lock, expiry, allowlist or gate can be listed")]
+    InvalidListing,
+
+    #[msg("Market has no orders within the requested limits")]
+    NoMarketOrders,
+
+    #[msg("Signer is not the maker or the position holder")]
+    Unauthorized,
+
+    #[msg("Escrow has a position token, pass the holder's position account")]
+    MissingPosition,
+
+    #[msg("Position account does not hold the escrow's position token")]
+    InvalidPosition,
+
+    #[msg("Position escrows cannot be standing orders, expire, be listed, use a proceeds recipient or recovery authority, or change terms after make")]
+    InvalidPositionTerms,
+
+    #[msg("Too many reference keys")]
+    TooManyReferences,
+
+    #[msg("A reference key of the escrow is missing from the take")]
+    MissingReference,
+
+    #[msg("Recovery authority must be set with at least the minimum timeout")]
+    InvalidRecovery,
+
+    #[msg("Recovery is not available until the maker has been inactive for the timeout")]
+    RecoveryNotAvailable,
+
+    #[msg("Escrow does not accept reservations")]
+    ReservationsDisabled,
+
+    #[msg("Reservation terms or duration are invalid")]
+    InvalidReservation,
+
+    #[msg("Escrow has an outstanding reservation")]
+    EscrowReserved,
+
+    #[msg("Reservation has not lapsed")]
+    ReservationActive,
+
+    #[msg("Reservation accounts are required")]
+    MissingReservation,
+
+    #[msg("Escrow must be taken with commit_take and reveal_take")]
+    CommitRequired,
+
+    #[msg("Escrow does not use commit-reveal taking")]
+    CommitRevealDisabled,
+
+    #[msg("The taker already has a commitment pending on this escrow")]
+    EscrowCommitted,
+
+    #[msg("Revealed salt does not match the taker's commitment")]
+    CommitmentMismatch,
+
+    #[msg("Commitment cannot be revealed yet")]
+    RevealTooEarly,
+
+    #[msg("Commitment reveal window has closed")]
+    CommitmentExpired,
+
+    #[msg("Taker cannot refer their own fill")]
+    SelfReferral,
+
+    #[msg("Pass both position_mint and maker_position, or neither")]
+    IncompletePositionAccounts,
+
+    #[msg("Reference keys must be passed read-only")]
+    WritableReference,
+
+    #[msg("Only fixed-price escrows can be reserved")]
+    ReservationNotFixedPrice,
+
+    #[msg("Market book is full, pass the escrow of its worst order to evict it")]
+    MissingEvictedOrder,
 }
-    
\ No newline at end of file
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct RecoveryAuthoritySet {
+    pub escrow: Pubkey,
+    /// `None` when the maker cleared it.
+    pub recovery_authority: Option<Pubkey>,
+    pub recovery_account: Pubkey,
+    pub timeout: u64,
+}
+
+#[event]
+pub struct EscrowRecovered {
+    pub escrow: Pubkey,
+    pub recovery_authority: Pubkey,
+    pub recovery_account: Pubkey,
+    pub amount_a: u64,
+}
+
+#[event]
+pub struct ReservationMade {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub bond: u64,
+    pub reserved_until: u64,
+}
+
+#[event]
+pub struct ReservationReleased {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    /// Paid to the maker's proceeds.
+    pub forfeited: u64,
+    /// Returned to the taker.
+    pub returned: u64,
+}
+
+#[event]
+pub struct TakeCommitted {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    /// First slot `reveal_take` can land at.
+    pub reveal_slot: u64,
+}
+
+#[event]
+pub struct TakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub referrer: Option<Pubkey>,
+    pub referrer_fee: u64,
+    /// The escrow's memo, all zeroes when none was set.
+    pub memo: [u8; 32],
+}
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::state::{Auction, Escrow};
+use crate::errors::EscrowError;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct Bid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = bidder,
+    )]
+    pub bidder_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// Refund destination for the bid being replaced, required once a bid exists.
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = auction.highest_bidder,
+    )]
+    pub previous_bidder_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(has_one = mint_b @ EscrowError::MintMismatch)]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        has_one = escrow,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+//Refund the outbid bidder
+//Lock the new bid in the bid vault
+//Extend the auction when the bid lands close to the end
+impl<'info> Bid<'info> {
+    pub fn bid(&mut self, amount: u64) -> Result<()> {
+        let current_slot = current_clock()?.slot;
+
+        require!(current_slot < self.auction.end_slot, EscrowError::AuctionEnded);
+
+        let minimum = if self.auction.has_bid() {
+            self.auction.highest_bid.checked_add(self.auction.min_increment.max(1)).ok_or(EscrowError::ArithmeticOverflow)?
+        } else {
+            self.auction.min_bid
+        };
+        require!(amount >= minimum, EscrowError::BidTooLow);
+
+        if self.auction.has_bid() {
+            self.refund_previous_bid()?;
+        }
+
+        let cpi_accounts = TransferChecked {
+            from: self.bidder_ata_b.to_account_info(),
+            to: self.bid_vault.to_account_info(),
+            authority: self.bidder.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+
+        self.auction.highest_bidder = self.bidder.key();
+        self.auction.highest_bid = amount;
+
+        if self.auction.end_slot - current_slot < self.auction.extension {
+            self.auction.end_slot = current_slot.checked_add(self.auction.extension).ok_or(EscrowError::ArithmeticOverflow)?;
+        }
+
+        Ok(())
+    }
+
+    // Exactly one refund per bid keeps the instruction CU-bounded
+    fn refund_previous_bid(&mut self) -> Result<()> {
+        let previous_bidder_ata_b = self.previous_bidder_ata_b.as_ref().ok_or(EscrowError::MissingPreviousBidder)?;
+
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"auction",
+            escrow_key.as_ref(),
+            &[self.auction.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.bid_vault.to_account_info(),
+            to: previous_bidder_ata_b.to_account_info(),
+            authority: self.auction.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.auction.highest_bid, self.mint_b.decimals)
+    }
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::state::TakeCommitment;
+
+/// Returns a commitment's rent to its taker. A revealed commitment is
+/// closed by the take itself; this covers ones that lapsed or whose escrow
+/// went to someone else.
+#[derive(Accounts)]
+pub struct CloseCommitment<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker,
+        seeds = [b"commitment", commitment.escrow.as_ref(), taker.key().as_ref()],
+        bump = commitment.bump,
+    )]
+    pub commitment: Account<'info, TakeCommitment>,
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+
+use crate::state::{Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::{current_clock, move_lamports};
+
+#[derive(Accounts)]
+pub struct CloseStale<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.load()?.rent_payer)]
+    pub rent_recipient: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_recipient,
+        has_one = maker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Return the vault to the maker
+//Close the vault and escrow
+//Pay the bounty to the cranker
+impl<'info> CloseStale<'info> {
+    pub fn return_vault_and_close(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.is_expired(&current_clock()?), EscrowError::EscrowNotExpired);
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_recipient.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+
+    pub fn pay_bounty(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        move_lamports(&self.escrow.to_account_info(), &self.cranker.to_account_info(), escrow.bounty)
+    }
+}
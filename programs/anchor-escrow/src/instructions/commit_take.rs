@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, TakeCommitment};
+use crate::errors::EscrowError;
+use crate::events::TakeCommitted;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct CommitTake<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        seeds = [b"commitment", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+        space = 8 + TakeCommitment::INIT_SPACE,
+    )]
+    pub commitment: Account<'info, TakeCommitment>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CommitTake<'info> {
+    /// Records the taker's `commitment` in their own account, so any number
+    /// of takers can commit at once and the earliest opens for reveal
+    /// first. A taker may commit again once their last window closes.
+    pub fn commit_take(&mut self, commitment: [u8; 32], bumps: &CommitTakeBumps) -> Result<()> {
+        require!(self.escrow.load()?.requires_commit(), EscrowError::CommitRevealDisabled);
+
+        let clock = current_clock()?;
+
+        require!(!self.commitment.is_active(&clock)?, EscrowError::EscrowCommitted);
+
+        self.commitment.set_inner(TakeCommitment {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            commitment,
+            committed_at: clock.slot,
+            bump: bumps.commitment,
+        });
+
+        emit!(TakeCommitted {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            reveal_slot: self.commitment.reveal_slot()?,
+        });
+
+        Ok(())
+    }
+}
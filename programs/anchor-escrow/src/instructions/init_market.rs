@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::state::Market;
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"market", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump,
+        space = 8 + Market::INIT_SPACE,
+    )]
+    pub market: Account<'info, Market>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitMarket<'info> {
+    pub fn init_market(&mut self, bumps: &InitMarketBumps) -> Result<()> {
+        self.market.set_inner(Market {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            orders: vec![],
+            bump: bumps.market,
+        });
+
+        Ok(())
+    }
+}
@@ -1,13 +1,29 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{approve, mint_to, set_authority, spl_token_2022::instruction::AuthorityType, Approve, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+
+use crate::state::{DutchAuction, Escrow, Market, MarketOrder, Pricing, RecoveryConfig};
+use crate::errors::EscrowError;
+use crate::constants::{MAX_BPS, MAX_REFERENCES};
+use crate::events::RecoveryAuthoritySet;
+use crate::utils::{current_clock, metaplex_verified_collection, token_2022_collection};
+/// External identifiers stored on the escrow for reconciling its fill.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EscrowReference {
+    pub memo: [u8; 32],
+    /// At most `MAX_REFERENCES` keys that must be passed to `take`.
+    pub references: Vec<Pubkey>,
+}
 
-use crate::state::Escrow;
-use anchor_lang::solana_program::sysvar::clock::Clock;
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct Make<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
+    /// Funds the escrow and vault rent and gets it back when they close.
+    /// Omit when nobody is sponsoring the rent and the maker pays it.
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
     pub mint_a: InterfaceAccount<'info, Mint>,
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
@@ -16,45 +32,349 @@ pub struct Make<'info> {
         associated_token::authority = maker,
     )]
     pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Metaplex metadata PDA of mint_a, parsed in `validate_nft` when a
+    /// collection is requested for an SPL Token mint.
+    pub mint_a_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: wallet or mint_b token account that receives the proceeds,
+    /// checked in `set_proceeds_recipient`. Defaults to the maker.
+    pub proceeds_recipient: Option<UncheckedAccount<'info>>,
     #[account(
         init,
-        payer = maker,
+        payer = rent_payer.as_ref().unwrap_or(&maker),
         seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
         bump,
-        space = 8 + Escrow::INIT_SPACE,
+        space = 8 + Escrow::LEN,
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub escrow: AccountLoader<'info, Escrow>,
     #[account(
         init,
-        payer = maker,
+        payer = rent_payer.as_ref().unwrap_or(&maker),
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// Book to list the escrow in, if any.
+    #[account(
+        mut,
+        seeds = [b"market", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Option<Account<'info, Market>>,
+    /// 1-of-1 position token, minted to `maker_position` when the maker wants
+    /// a transferable claim on the escrow's proceeds and refund. The mint
+    /// outlives the escrow: it is created without Token-2022's mint close
+    /// authority, so it cannot be closed and its rent is not reclaimed.
+    #[account(
+        init,
+        payer = rent_payer.as_ref().unwrap_or(&maker),
+        seeds = [b"position", escrow.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = escrow,
+        mint::token_program = token_program,
+    )]
+    pub position_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = rent_payer.as_ref().unwrap_or(&maker),
+        associated_token::mint = position_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_position: Option<InterfaceAccount<'info, TokenAccount>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
+    pub fn validate_nft(&self, deposit: u64, collection: Option<Pubkey>) -> Result<()> {
+        let is_nft = self.mint_a.decimals == 0 && self.mint_a.supply == 1;
+
+        if is_nft {
+            require!(deposit == 1, EscrowError::NftDepositMustBeOne);
+        }
+
+        let Some(collection) = collection else {
+            return Ok(());
+        };
+
+        require!(is_nft, EscrowError::NotAnNft);
+
+        let mint_info = self.mint_a.to_account_info();
+        let verified = if *mint_info.owner == anchor_spl::token_2022::ID {
+            token_2022_collection(&mint_info)?
+        } else {
+            let metadata = self.mint_a_metadata.as_ref().ok_or(EscrowError::MissingNftMetadata)?;
+            metaplex_verified_collection(&metadata.to_account_info(), &self.mint_a.key())?
+        };
+
+        require!(verified == Some(collection), EscrowError::CollectionMismatch);
+
+        Ok(())
+    }
+
+    // The discriminator is only written on exit, so every write to the new
+    // escrow during `make` goes through `load_init` rather than `load_mut`.
     pub fn init_escrow(&mut self, seed: u64, receive: u64,lock_period:u64, bumps: &MakeBumps) -> Result<()> {
-        let clock = Clock::get()?;
-        
-        self.escrow.set_inner(Escrow {
+        require!(receive > 0, EscrowError::ZeroAmount);
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::InvalidTerms);
+
+        let clock = current_clock()?;
+        clock.slot.checked_add(lock_period).ok_or(EscrowError::ArithmeticOverflow)?;
+
+        *self.escrow.load_init()? = Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
             receive,
-            bump: bumps.escrow,
             lock_period,
             start_time:  clock.slot as u64,
+            expires_at: 0,
+            bounty: 0,
+            pricing: Pricing::Fixed.into(),
+            rent_payer: self.rent_payer.as_ref().unwrap_or(&self.maker).key(),
+            proceeds_recipient: self.maker.key(),
+            taker_allowlist_root: [0; 32],
+            gate_mint: Pubkey::default(),
+            gate_min_balance: 0,
+            fill_amount: 0,
+            remaining: 0,
+            max_referrer_fee_bps: 0,
+            bump: bumps.escrow,
+            position_mint: Pubkey::default(),
+            memo: [0; 32],
+            references: [Pubkey::default(); MAX_REFERENCES],
+            recovery_authority: Pubkey::default(),
+            recovery_account: Pubkey::default(),
+            recovery_timeout: 0,
+            last_activity: clock.slot,
+            reservation_bond: 0,
+            reservation_max_slots: 0,
+            reserved_by: Pubkey::default(),
+            reserved_until: 0,
+            reservation_forfeit_bps: 0,
+            requires_commit: 0,
+            listed: 0,
+            _padding: [0; 1],
+        };
+
+        Ok(())
+    }
+
+    pub fn set_referrer_fee_cap(&mut self, max_referrer_fee_bps: u16) -> Result<()> {
+        require!(max_referrer_fee_bps <= MAX_BPS, EscrowError::ReferrerFeeTooHigh);
+
+        self.escrow.load_init()?.max_referrer_fee_bps = max_referrer_fee_bps;
+
+        Ok(())
+    }
+
+    pub fn set_proceeds_recipient(&mut self) -> Result<()> {
+        let Some(recipient) = &self.proceeds_recipient else {
+            return Ok(());
+        };
+
+        if *recipient.owner == self.token_program.key() {
+            let account = TokenAccount::try_deserialize(&mut &recipient.try_borrow_data()?[..])?;
+            require_keys_eq!(account.mint, self.mint_b.key(), EscrowError::MintMismatch);
+        }
+
+        self.escrow.load_init()?.proceeds_recipient = recipient.key();
+
+        Ok(())
+    }
+
+    pub fn set_reference(&mut self, reference: Option<EscrowReference>) -> Result<()> {
+        let Some(reference) = reference else {
+            return Ok(());
+        };
+
+        require!(reference.references.len() <= MAX_REFERENCES, EscrowError::TooManyReferences);
+
+        let mut escrow = self.escrow.load_init()?;
+        escrow.memo = reference.memo;
+        escrow.references[..reference.references.len()].copy_from_slice(&reference.references);
+
+        Ok(())
+    }
+
+    pub fn set_recovery(&mut self, recovery: Option<RecoveryConfig>) -> Result<()> {
+        let Some(recovery) = recovery else {
+            return Ok(());
+        };
+
+        self.escrow.load_init()?.set_recovery(Some(recovery))?;
+
+        emit!(RecoveryAuthoritySet {
+            escrow: self.escrow.key(),
+            recovery_authority: Some(recovery.authority),
+            recovery_account: recovery.account,
+            timeout: recovery.timeout,
         });
 
         Ok(())
     }
 
+    pub fn attach_bounty(&mut self, expiry: u64, bounty: u64) -> Result<()> {
+        if expiry == 0 {
+            require!(bounty == 0, EscrowError::BountyWithoutExpiry);
+            return Ok(());
+        }
+
+        {
+            let mut escrow = self.escrow.load_init()?;
+            escrow.expires_at = escrow.start_time.checked_add(expiry).ok_or(EscrowError::ArithmeticOverflow)?;
+            escrow.bounty = bounty;
+        }
+
+        if bounty == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.maker.to_account_info(),
+            to: self.escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+        transfer(cpi_ctx, bounty)
+    }
+
+    pub fn init_dutch_pricing(&mut self, auction: DutchAuction) -> Result<()> {
+        auction.validate()?;
+
+        self.escrow.load_init()?.set_pricing(Pricing::Dutch(auction));
+
+        Ok(())
+    }
+
+    /// Turns the escrow into a standing order selling `fill_amount` per take,
+    /// `total_quantity` in all. Whatever is not deposited up front is approved
+    /// for the escrow to pull from `maker_ata_a` with `refill`, replacing any
+    /// delegate already set on that account.
+    pub fn init_standing_order(&mut self, deposit: u64, fill_amount: u64, total_quantity: u64) -> Result<()> {
+        require!(fill_amount > 0, EscrowError::ZeroAmount);
+        require!(
+            fill_amount <= total_quantity && deposit <= total_quantity,
+            EscrowError::InvalidTerms
+        );
+
+        {
+            let mut escrow = self.escrow.load_init()?;
+            escrow.fill_amount = fill_amount;
+            escrow.remaining = total_quantity;
+        }
+
+        let allowance = total_quantity - deposit;
+        if allowance == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Approve {
+            to: self.maker_ata_a.to_account_info(),
+            delegate: self.escrow.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        approve(cpi_ctx, allowance)
+    }
+
+    /// Mints the escrow's position token to the maker and removes the mint
+    /// authority, so exactly one ever exists.
+    pub fn mint_position(&mut self) -> Result<()> {
+        let (position_mint, maker_position) = match (&self.position_mint, &self.maker_position) {
+            (Some(position_mint), Some(maker_position)) => (position_mint, maker_position),
+            (None, None) => return Ok(()),
+            _ => return err!(EscrowError::IncompletePositionAccounts),
+        };
+
+        let (seed, bump) = {
+            let mut escrow = self.escrow.load_init()?;
+
+            // Recovery is refused on position escrows, so it cannot be configured on one either
+            require!(
+                !escrow.is_standing()
+                    && escrow.expires_at == 0
+                    && escrow.proceeds_recipient == escrow.maker
+                    && escrow.recovery().is_none(),
+                EscrowError::InvalidPositionTerms
+            );
+
+            escrow.position_mint = position_mint.key();
+            (escrow.seed, escrow.bump)
+        };
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &seed.to_le_bytes()[..],
+            &[bump]
+        ]];
+
+        let cpi_accounts = MintTo {
+            mint: position_mint.to_account_info(),
+            to: maker_position.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        mint_to(cpi_ctx, 1)?;
+
+        let cpi_accounts = SetAuthority {
+            current_authority: self.escrow.to_account_info(),
+            account_or_mint: position_mint.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        set_authority(cpi_ctx, AuthorityType::MintTokens, None)
+    }
+
+    /// Lists the escrow in its market, if passed. When the book is full and
+    /// this order evicts the worst one, that order's escrow must be the first
+    /// remaining account so it can be marked unlisted.
+    pub fn list_on_market(&mut self, deposit: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let Some(market) = &mut self.market else {
+            return Ok(());
+        };
+
+        let mut escrow = self.escrow.load_init()?;
+
+        require!(
+            escrow.pricing() == Pricing::Fixed
+                && !escrow.is_standing()
+                && !escrow.has_position()
+                && !escrow.has_references()
+                && escrow.lock_period == 0
+                && escrow.expires_at == 0,
+            EscrowError::InvalidListing
+        );
+
+        let evicted = market.insert(MarketOrder {
+            escrow: self.escrow.key(),
+            amount_a: deposit,
+            receive: escrow.receive,
+        })?;
+        escrow.listed = 1;
+
+        if let Some(evicted) = evicted {
+            let account = remaining_accounts.first().ok_or(EscrowError::MissingEvictedOrder)?;
+            require_keys_eq!(account.key(), evicted.escrow, EscrowError::MissingEvictedOrder);
+            AccountLoader::<Escrow>::try_from(account)?.load_mut()?.listed = 0;
+        }
+
+        Ok(())
+    }
+
     pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+        require!(deposit > 0, EscrowError::ZeroAmount);
+
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
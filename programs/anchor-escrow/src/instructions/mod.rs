@@ -1,7 +1,43 @@
+pub mod bid;
+pub mod close_commitment;
+pub mod close_stale;
+pub mod commit_take;
+pub mod init_market;
 pub mod make;
+pub mod refill;
+pub mod recover;
 pub mod refund;
+pub mod release_reservation;
+pub mod reserve;
+pub mod rotate_recovery;
+pub mod set_commit_reveal;
+pub mod set_reservation_terms;
+pub mod set_taker_allowlist;
+pub mod set_take_gate;
+pub mod settle;
+pub mod start_auction;
 pub mod take;
+pub mod take_many;
+pub mod take_standing;
 
+pub use bid::*;
+pub use close_commitment::*;
+pub use close_stale::*;
+pub use commit_take::*;
+pub use init_market::*;
 pub use make::*;
+pub use refill::*;
+pub use recover::*;
 pub use refund::*;
-pub use take::*;
\ No newline at end of file
+pub use release_reservation::*;
+pub use reserve::*;
+pub use rotate_recovery::*;
+pub use set_commit_reveal::*;
+pub use set_reservation_terms::*;
+pub use set_taker_allowlist::*;
+pub use set_take_gate::*;
+pub use settle::*;
+pub use start_auction::*;
+pub use take::*;
+pub use take_many::*;
+pub use take_standing::*;
\ No newline at end of file
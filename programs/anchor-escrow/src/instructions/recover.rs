@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+
+use crate::state::{Escrow, Market, Pricing};
+use crate::errors::EscrowError;
+use crate::events::EscrowRecovered;
+use crate::utils::{current_clock, move_lamports};
+
+#[derive(Accounts)]
+pub struct Recover<'info> {
+    recovery_authority: Signer<'info>,
+    #[account(mut)]
+    maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.load()?.rent_payer)]
+    rent_recipient: SystemAccount<'info>,
+    mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        address = escrow.load()?.recovery_account @ EscrowError::InvalidRecovery,
+        token::mint = mint_a,
+        token::token_program = token_program,
+    )]
+    recovery_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_recipient,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = maker,
+        has_one = recovery_authority @ EscrowError::Unauthorized,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    vault: InterfaceAccount<'info, TokenAccount>,
+    /// Book the escrow is listed in, if any.
+    #[account(
+        mut,
+        seeds = [b"market", mint_a.key().as_ref(), escrow.load()?.mint_b.as_ref()],
+        bump = market.bump,
+    )]
+    market: Option<Account<'info, Market>>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Recover<'info> {
+    /// Escrows with a position token are refunded by its holder instead, so
+    /// only a maker's own claim can be recovered.
+    pub fn check_recovery(&self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+        require!(escrow.can_recover(&current_clock()?)?, EscrowError::RecoveryNotAvailable);
+
+        Ok(())
+    }
+
+    pub fn delist(&mut self) -> Result<()> {
+        if !self.escrow.load()?.is_listed() {
+            return Ok(());
+        }
+
+        let market = self.market.as_mut().ok_or(EscrowError::MissingMarket)?;
+        market.remove(&self.escrow.key())?;
+
+        Ok(())
+    }
+
+    pub fn recover_and_close_vault(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let amount_a = self.vault.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.recovery_account.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, amount_a, self.mint_a.decimals)?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_recipient.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        move_lamports(&self.escrow.to_account_info(), &self.maker.to_account_info(), escrow.bounty)?;
+
+        emit!(EscrowRecovered {
+            escrow: self.escrow.key(),
+            recovery_authority: self.recovery_authority.key(),
+            recovery_account: self.recovery_account.key(),
+            amount_a,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::state::Escrow;
+use crate::errors::EscrowError;
+
+/// Tops a standing order's vault back up from the maker's ATA, using the
+/// allowance approved to the escrow at `make_standing`. Anyone can crank it.
+#[derive(Accounts)]
+pub struct Refill<'info> {
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = maker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Refill<'info> {
+    pub fn refill(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.is_standing(), EscrowError::NotStandingOrder);
+
+        // The vault never holds more than the order's remaining quantity
+        let allowance = if self.maker_ata_a.delegate == Some(self.escrow.key()).into() {
+            self.maker_ata_a.delegated_amount
+        } else {
+            0
+        };
+        let amount = escrow
+            .remaining
+            .saturating_sub(self.vault.amount)
+            .min(allowance)
+            .min(self.maker_ata_a.amount);
+
+        require!(amount > 0, EscrowError::NothingToRefill);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    }
+}
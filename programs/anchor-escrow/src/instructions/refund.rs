@@ -1,52 +1,92 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account, revoke, Revoke}};
 
-use crate::state::Escrow;
+use crate::state::{Escrow, Market, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::{move_lamports, position_holder};
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
+    /// The maker, or the holder of the escrow's position token.
     #[account(mut)]
-    maker: Signer<'info>,
+    authority: Signer<'info>,
+    #[account(mut)]
+    maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.load()?.rent_payer)]
+    rent_recipient: SystemAccount<'info>,
     mint_a: InterfaceAccount<'info, Mint>,
     #[account(
-        mut,
+        init_if_needed,
+        payer = authority,
         associated_token::mint = mint_a,
-        associated_token::authority = maker,
+        associated_token::authority = authority,
     )]
-    maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    authority_ata_a: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
-        close = maker,
-        has_one = mint_a,
+        close = rent_recipient,
+        has_one = mint_a @ EscrowError::MintMismatch,
         has_one = maker,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
-        bump = escrow.bump,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub escrow: AccountLoader<'info, Escrow>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
     )]
     vault: InterfaceAccount<'info, TokenAccount>,
+    /// Book the escrow is listed in, if any.
+    #[account(
+        mut,
+        seeds = [b"market", mint_a.key().as_ref(), escrow.load()?.mint_b.as_ref()],
+        bump = market.bump,
+    )]
+    market: Option<Account<'info, Market>>,
+    /// Required for escrows with a position token, burned by the refund.
+    #[account(mut, address = escrow.load()?.position_mint @ EscrowError::InvalidPosition)]
+    position_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// The authority's token account holding the position, closed by the refund.
+    #[account(mut)]
+    position: Option<InterfaceAccount<'info, TokenAccount>>,
+    associated_token_program: Program<'info, AssociatedToken>,
     token_program: Interface<'info, TokenInterface>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
+    pub fn check_authority(&self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require_keys_eq!(
+            self.authority.key(),
+            position_holder(&escrow, self.position.as_ref())?,
+            EscrowError::Unauthorized
+        );
+
+        Ok(())
+    }
+
     pub fn refund_and_close_vault(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        // A lapsed reservation still holds the taker's bond until released.
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump]
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
         ]];
         
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
             from: self.vault.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
+            to: self.authority_ata_a.to_account_info(),
             mint: self.mint_a.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
@@ -59,14 +99,73 @@ impl<'info> Refund<'info> {
 
         let cpi_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: self.rent_recipient.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
         close_account(cpi_context)?;
-        
+
+        move_lamports(&self.escrow.to_account_info(), &self.maker.to_account_info(), escrow.bounty)
+    }
+
+    pub fn delist(&mut self) -> Result<()> {
+        if !self.escrow.load()?.is_listed() {
+            return Ok(());
+        }
+
+        let market = self.market.as_mut().ok_or(EscrowError::MissingMarket)?;
+        market.remove(&self.escrow.key())?;
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Drops the refill allowance a standing order left on the maker's ATA.
+    /// Standing orders have no position, so the authority is the maker.
+    pub fn revoke_refill_allowance(&mut self) -> Result<()> {
+        if self.authority_ata_a.delegate != Some(self.escrow.key()).into() {
+            return Ok(());
+        }
+
+        let cpi_accounts = Revoke {
+            source: self.authority_ata_a.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        revoke(cpi_ctx)
+    }
+
+    /// Burns the position token and returns its account rent to the holder.
+    /// The mint stays open with zero supply, as it has no close authority.
+    pub fn burn_position(&mut self) -> Result<()> {
+        if !self.escrow.load()?.has_position() {
+            return Ok(());
+        }
+
+        let position_mint = self.position_mint.as_ref().ok_or(EscrowError::MissingPosition)?;
+        let position = self.position.as_ref().ok_or(EscrowError::MissingPosition)?;
+
+        let cpi_accounts = Burn {
+            mint: position_mint.to_account_info(),
+            from: position.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        burn(cpi_ctx, 1)?;
+
+        let cpi_accounts = CloseAccount {
+            account: position.to_account_info(),
+            destination: self.authority.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        close_account(cpi_ctx)
+    }
+}
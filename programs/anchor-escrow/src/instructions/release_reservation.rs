@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+
+use crate::state::{Escrow, Reservation};
+use crate::errors::EscrowError;
+use crate::constants::MAX_BPS;
+use crate::events::ReservationReleased;
+use crate::utils::{current_clock, proceeds_account};
+
+#[derive(Accounts)]
+pub struct ReleaseReservation<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    /// Gets back the bond not forfeited and the reservation rent.
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the escrow's `proceeds_recipient`, paid the forfeited bond.
+    #[account(mut, address = escrow.load()?.proceeds_recipient @ EscrowError::ProceedsRecipientMismatch)]
+    pub proceeds_recipient: UncheckedAccount<'info>,
+    /// Omit when `proceeds_recipient` is itself a mint_b token account.
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_b,
+        associated_token::authority = proceeds_recipient,
+    )]
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = mint_b @ EscrowError::MintMismatch,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump = reservation.bump,
+    )]
+    pub reservation: Account<'info, Reservation>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = reservation,
+    )]
+    pub reservation_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Pay the forfeited share of the bond to the maker
+//Return the rest to the taker
+//Close the reservation and free the escrow
+impl<'info> ReleaseReservation<'info> {
+    pub fn release(&mut self) -> Result<()> {
+        require!(current_clock()?.slot >= self.reservation.expires_at, EscrowError::ReservationActive);
+
+        let bond = self.reservation.bond;
+        let forfeited = (bond as u128 * self.reservation.forfeit_bps as u128 / MAX_BPS as u128) as u64;
+        let returned = bond - forfeited;
+
+        let proceeds = proceeds_account(
+            &self.proceeds_recipient.to_account_info(),
+            self.maker_ata_b.as_ref(),
+            &self.mint_b.key(),
+            &self.token_program.key(),
+        )?;
+
+        if forfeited > 0 {
+            self.transfer_bond(proceeds, forfeited)?;
+        }
+        if returned > 0 {
+            self.transfer_bond(self.taker_ata_b.to_account_info(), returned)?;
+        }
+
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"reservation",
+            escrow_key.as_ref(),
+            &[self.reservation.bump]
+        ]];
+
+        let cpi_accounts = CloseAccount {
+            account: self.reservation_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.reservation.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.reserved_by = Pubkey::default();
+        escrow.reserved_until = 0;
+
+        emit!(ReservationReleased {
+            escrow: escrow_key,
+            taker: self.taker.key(),
+            forfeited,
+            returned,
+        });
+
+        Ok(())
+    }
+
+    fn transfer_bond(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"reservation",
+            escrow_key.as_ref(),
+            &[self.reservation.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.reservation_vault.to_account_info(),
+            to,
+            authority: self.reservation.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)
+    }
+}
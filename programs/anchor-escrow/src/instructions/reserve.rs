@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+
+use crate::state::{Escrow, Pricing, Reservation};
+use crate::errors::EscrowError;
+use crate::events::ReservationMade;
+use crate::utils::{check_take_gate, current_clock};
+
+#[derive(Accounts)]
+pub struct Reserve<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump,
+        space = 8 + Reservation::INIT_SPACE,
+    )]
+    pub reservation: Account<'info, Reservation>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = reservation,
+    )]
+    pub reservation_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Reserve<'info> {
+    /// Locks the escrow to the taker for `slots`, holding the taker's bond
+    /// in the reservation's ATA. Gated escrows take the taker's gate account
+    /// as the first remaining account, as `take` does.
+    pub fn reserve(&mut self, slots: u64, bumps: &ReserveBumps, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(escrow.reservation_bond > 0, EscrowError::ReservationsDisabled);
+        require!(escrow.pricing() == Pricing::Fixed, EscrowError::ReservationNotFixedPrice);
+        require!(!escrow.is_standing(), EscrowError::StandingOrder);
+        require!(!escrow.requires_commit(), EscrowError::CommitRequired);
+        require!(slots > 0 && slots <= escrow.reservation_max_slots, EscrowError::InvalidReservation);
+        // Reserving an escrow the taker could never take would only lock out everyone else
+        require!(!escrow.has_allowlist(), EscrowError::AllowlistProofRequired);
+        check_take_gate(&escrow, &self.taker.key(), remaining_accounts)?;
+
+        let expires_at = current_clock()?
+            .slot
+            .checked_add(slots)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.reservation_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, escrow.reservation_bond, self.mint_b.decimals)?;
+
+        self.reservation.set_inner(Reservation {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            bond: escrow.reservation_bond,
+            forfeit_bps: escrow.reservation_forfeit_bps,
+            expires_at,
+            bump: bumps.reservation,
+        });
+
+        escrow.reserved_by = self.taker.key();
+        escrow.reserved_until = expires_at;
+
+        emit!(ReservationMade {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            bond: escrow.reservation_bond,
+            reserved_until: expires_at,
+        });
+
+        Ok(())
+    }
+}
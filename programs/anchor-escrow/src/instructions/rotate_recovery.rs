@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, RecoveryConfig};
+use crate::errors::EscrowError;
+use crate::events::RecoveryAuthoritySet;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct RotateRecovery<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> RotateRecovery<'info> {
+    /// Replaces the recovery authority, or clears it with `None`. Counts as
+    /// maker activity, so it also restarts the recovery timeout.
+    pub fn rotate_recovery(&mut self, recovery: Option<RecoveryConfig>) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+
+        escrow.set_recovery(recovery)?;
+        escrow.last_activity = current_clock()?.slot;
+
+        emit!(RecoveryAuthoritySet {
+            escrow: self.escrow.key(),
+            recovery_authority: recovery.map(|recovery| recovery.authority),
+            recovery_account: escrow.recovery_account,
+            timeout: escrow.recovery_timeout,
+        });
+
+        Ok(())
+    }
+}
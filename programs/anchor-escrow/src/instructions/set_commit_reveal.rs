@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct SetCommitReveal<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> SetCommitReveal<'info> {
+    /// Requires takers to commit before they reveal and fill, so a pending
+    /// take cannot be copied by someone else.
+    pub fn set_commit_reveal(&mut self, required: bool) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.is_standing(), EscrowError::StandingOrder);
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+        require!(!escrow.is_listed(), EscrowError::EscrowListed);
+
+        escrow.requires_commit = required as u8;
+        escrow.last_activity = current_clock()?.slot;
+
+        Ok(())
+    }
+}
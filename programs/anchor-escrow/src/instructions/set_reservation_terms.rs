@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::constants::MAX_BPS;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct SetReservationTerms<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> SetReservationTerms<'info> {
+    /// Lets takers reserve the escrow for up to `max_slots` by posting `bond`
+    /// of mint_b. A zero bond disables reservations.
+    pub fn set_reservation_terms(&mut self, bond: u64, max_slots: u64, forfeit_bps: u16) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.is_standing(), EscrowError::StandingOrder);
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+        require!(!escrow.is_listed(), EscrowError::EscrowListed);
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+
+        escrow.last_activity = current_clock()?.slot;
+
+        if bond == 0 {
+            escrow.reservation_bond = 0;
+            escrow.reservation_max_slots = 0;
+            escrow.reservation_forfeit_bps = 0;
+            return Ok(());
+        }
+
+        require!(max_slots > 0 && forfeit_bps <= MAX_BPS, EscrowError::InvalidReservation);
+
+        escrow.reservation_bond = bond;
+        escrow.reservation_max_slots = max_slots;
+        escrow.reservation_forfeit_bps = forfeit_bps;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct SetTakeGate<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> SetTakeGate<'info> {
+    /// Only takers holding at least `min_balance` of `gate_mint` can fill.
+    /// The default pubkey removes the gate.
+    pub fn set_take_gate(&mut self, gate_mint: Pubkey, min_balance: u64) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+
+        require!(!escrow.is_listed(), EscrowError::EscrowListed);
+
+        escrow.last_activity = current_clock()?.slot;
+
+        if gate_mint == Pubkey::default() {
+            escrow.gate_mint = Pubkey::default();
+            escrow.gate_min_balance = 0;
+            return Ok(());
+        }
+
+        require!(min_balance > 0, EscrowError::ZeroAmount);
+
+        escrow.gate_mint = gate_mint;
+        escrow.gate_min_balance = min_balance;
+
+        Ok(())
+    }
+}
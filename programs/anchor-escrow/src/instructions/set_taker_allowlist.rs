@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct SetTakerAllowlist<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> SetTakerAllowlist<'info> {
+    /// Restricts takers to the leaves of `root`. An all-zero root opens the
+    /// escrow to anyone again.
+    pub fn set_taker_allowlist(&mut self, root: [u8; 32]) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.has_position(), EscrowError::InvalidPositionTerms);
+
+        require!(!escrow.is_listed(), EscrowError::EscrowListed);
+
+        escrow.taker_allowlist_root = root;
+        escrow.last_activity = current_clock()?.slot;
+
+        Ok(())
+    }
+}
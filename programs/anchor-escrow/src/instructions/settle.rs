@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+
+use crate::state::{Auction, Escrow};
+use crate::errors::EscrowError;
+use crate::utils::{current_clock, move_lamports, proceeds_account};
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.load()?.rent_payer)]
+    pub rent_recipient: SystemAccount<'info>,
+    /// CHECK: highest bidder, or the maker when nobody bid
+    #[account(address = auction.winner(maker.key()))]
+    pub winner: UncheckedAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = winner,
+    )]
+    pub winner_ata_a: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the escrow's `proceeds_recipient`, paid the winning bid.
+    #[account(mut, address = escrow.load()?.proceeds_recipient @ EscrowError::ProceedsRecipientMismatch)]
+    pub proceeds_recipient: UncheckedAccount<'info>,
+    /// Omit when `proceeds_recipient` is itself a mint_b token account.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = proceeds_recipient,
+    )]
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        close = rent_recipient,
+        has_one = maker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = escrow,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Pay the winning bid to the proceeds recipient
+//Release the vault to the winner
+//Close both vaults
+impl<'info> Settle<'info> {
+    pub fn settle(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+        require!(current_clock()?.slot >= self.auction.end_slot, EscrowError::AuctionNotEnded);
+
+        self.release_winning_bid()?;
+        self.release_vault()?;
+
+        move_lamports(&self.escrow.to_account_info(), &self.maker.to_account_info(), escrow.bounty)
+    }
+
+    fn release_winning_bid(&mut self) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"auction",
+            escrow_key.as_ref(),
+            &[self.auction.bump]
+        ]];
+
+        let proceeds = proceeds_account(
+            &self.proceeds_recipient.to_account_info(),
+            self.maker_ata_b.as_ref(),
+            &self.mint_b.key(),
+            &self.token_program.key(),
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.bid_vault.to_account_info(),
+            to: proceeds,
+            authority: self.auction.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.bid_vault.amount, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.bid_vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.auction.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+
+    fn release_vault(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.winner_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_recipient.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::state::{Auction, Escrow, Pricing};
+use crate::errors::EscrowError;
+use crate::utils::current_clock;
+
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump,
+        space = 8 + Auction::INIT_SPACE,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartAuction<'info> {
+    pub fn start_auction(&mut self, min_bid: u64, min_increment: u64, duration: u64, extension: u64, bumps: &StartAuctionBumps) -> Result<()> {
+        require!(self.escrow.load()?.pricing() == Pricing::Fixed, EscrowError::NotFixedPrice);
+        require!(!self.escrow.load()?.has_allowlist(), EscrowError::AllowlistProofRequired);
+        require!(!self.escrow.load()?.is_gated(), EscrowError::MissingGateAccount);
+        require!(!self.escrow.load()?.is_standing(), EscrowError::StandingOrder);
+        require!(!self.escrow.load()?.is_listed(), EscrowError::EscrowListed);
+        require!(!self.escrow.load()?.has_position(), EscrowError::InvalidPositionTerms);
+        require!(!self.escrow.load()?.is_reserved(), EscrowError::EscrowReserved);
+        require!(min_bid > 0 && duration > 0, EscrowError::InvalidAuctionParams);
+
+        let clock = current_clock()?;
+
+        self.auction.set_inner(Auction {
+            escrow: self.escrow.key(),
+            highest_bidder: Pubkey::default(),
+            highest_bid: 0,
+            min_bid,
+            min_increment,
+            end_slot: clock.slot.checked_add(duration).ok_or(EscrowError::ArithmeticOverflow)?,
+            extension,
+            bump: bumps.auction,
+        });
+
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.receive = min_bid;
+        escrow.set_pricing(Pricing::English);
+
+        Ok(())
+    }
+}
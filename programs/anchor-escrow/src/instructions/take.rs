@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
-use anchor_lang::solana_program::sysvar::clock::Clock;
-use crate::state::Escrow;
+use crate::state::{Escrow, Market, Pricing, Reservation, TakeCommitment};
 use crate::errors::*;
+use crate::utils::{allowlist_leaf, check_references, check_take_gate, current_clock, move_lamports, position_holder, proceeds_account, take_commitment, verify_proof};
+use crate::constants::MAX_BPS;
+use crate::events::TakeEvent;
 
 //Create context
 #[derive(Accounts)]
@@ -11,11 +13,18 @@ pub struct Take<'info> {
     pub taker: Signer<'info>,
     #[account(mut)]
     pub maker: SystemAccount<'info>,
+    /// Funds any token accounts created for the swap. Omit when nobody is
+    /// sponsoring the rent and the taker pays it.
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
+    /// Receives the escrow and vault rent, whoever paid it at `make`.
+    #[account(mut, address = escrow.load()?.rent_payer)]
+    pub rent_recipient: SystemAccount<'info>,
     pub mint_a: InterfaceAccount<'info, Mint>,
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
         init_if_needed,
-        payer = taker,
+        payer = rent_payer.as_ref().unwrap_or(&taker),
         associated_token::mint = mint_a,
         associated_token::authority = taker,
     )]
@@ -26,71 +35,329 @@ pub struct Take<'info> {
         associated_token::authority = taker,
     )]
     pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the escrow's `proceeds_recipient`, either the owner of
+    /// `maker_ata_b` or the mint_b token account paid directly. For an
+    /// escrow with a position token it is the position holder, checked in
+    /// `deposit`.
+    #[account(mut)]
+    pub proceeds_recipient: UncheckedAccount<'info>,
+    /// Omit when `proceeds_recipient` is itself a mint_b token account.
     #[account(
         init_if_needed,
-        payer = taker,
+        payer = rent_payer.as_ref().unwrap_or(&taker),
         associated_token::mint = mint_b,
-        associated_token::authority = maker,
+        associated_token::authority = proceeds_recipient,
     )]
-    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: only used as the authority of `referrer_ata_b`
+    pub referrer: Option<UncheckedAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = rent_payer.as_ref().unwrap_or(&taker),
+        associated_token::mint = mint_b,
+        associated_token::authority = referrer,
+    )]
+    pub referrer_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        close = maker,
+        close = rent_recipient,
         has_one = maker,
-        has_one = mint_a,
-        has_one = mint_b,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
-        bump = escrow.bump,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub escrow: AccountLoader<'info, Escrow>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// Book the escrow is listed in, if any.
+    #[account(
+        mut,
+        seeds = [b"market", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Option<Account<'info, Market>>,
+    /// Holder's position token account, for escrows with a position token.
+    pub position: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// The taker's reservation, required while the escrow is reserved.
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker @ EscrowError::EscrowReserved,
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump = reservation.bump,
+    )]
+    pub reservation: Option<Account<'info, Reservation>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = reservation,
+    )]
+    pub reservation_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// The taker's `commit_take`, required on commit-reveal escrows and
+    /// closed back to the taker.
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker @ EscrowError::CommitmentMismatch,
+        seeds = [b"commitment", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = commitment.bump,
+    )]
+    pub commitment: Option<Account<'info, TakeCommitment>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-//Deposit tokens from taker to maker
+/// Proves the taker is a leaf of the escrow's `taker_allowlist_root`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AllowlistProof {
+    pub proof: Vec<[u8; 32]>,
+    /// Most mint_a this taker may receive, as committed in their leaf.
+    pub cap: Option<u64>,
+}
+
+/// Amounts moved by a single fill, reported in `TakeEvent`.
+pub struct Fill {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub referrer_fee: u64,
+}
+
+//Deposit tokens from taker to maker and referrer
 //Transfer tokens from vault to taker
 //Close vault account
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {
-        let mut current_slot:u64 = 0;
-        if let Ok(v) = Clock::get() {
-            current_slot = v.slot;
-        }
-        
-        let unlock_slot = self.escrow.start_time.checked_add(self.escrow.lock_period).unwrap();
-        
+    pub fn deposit(&mut self, referrer_fee_bps: u16) -> Result<Fill> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.is_standing(), EscrowError::StandingOrder);
+
+        let proceeds_recipient = if escrow.has_position() {
+            position_holder(&escrow, self.position.as_ref())?
+        } else {
+            escrow.proceeds_recipient
+        };
+        require_keys_eq!(
+            self.proceeds_recipient.key(),
+            proceeds_recipient,
+            EscrowError::ProceedsRecipientMismatch
+        );
+
+        let clock = current_clock()?;
+
         require!(
-            current_slot >=  unlock_slot,
+            clock.slot >= escrow.unlock_slot()?,
             EscrowError::EscrowLocked
         );
-        
+        require!(
+            referrer_fee_bps <= escrow.max_referrer_fee_bps,
+            EscrowError::ReferrerFeeTooHigh
+        );
+
+        let amount_b = escrow.current_receive(&clock);
+        let referrer_fee = (amount_b as u128 * referrer_fee_bps as u128 / MAX_BPS as u128) as u64;
+
+        self.transfer_b(self.proceeds_account()?, amount_b - referrer_fee)?;
+
+        if referrer_fee > 0 {
+            // The fee comes out of the maker's proceeds, so a taker naming
+            // themselves would just be paying less
+            let referrer = self.referrer.as_ref().ok_or(EscrowError::MissingReferrer)?;
+            require_keys_neq!(referrer.key(), self.taker.key(), EscrowError::SelfReferral);
+
+            let referrer_ata_b = self.referrer_ata_b.as_ref().ok_or(EscrowError::MissingReferrer)?;
+            self.transfer_b(referrer_ata_b.to_account_info(), referrer_fee)?;
+        }
+
+        Ok(Fill {
+            amount_a: self.vault.amount,
+            amount_b,
+            referrer_fee,
+        })
+    }
+
+    /// Returns the reserving taker's bond so it counts toward the payment
+    /// `deposit` takes from `taker_ata_b`. Anyone else waits for the
+    /// reservation to lapse and be released.
+    pub fn redeem_reservation(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        if !escrow.is_reserved() {
+            return Ok(());
+        }
+
+        require!(
+            escrow.reserved_by == self.taker.key() && escrow.reservation_active(&current_clock()?),
+            EscrowError::EscrowReserved
+        );
+
+        let reservation = self.reservation.as_ref().ok_or(EscrowError::MissingReservation)?;
+        let reservation_vault = self.reservation_vault.as_ref().ok_or(EscrowError::MissingReservation)?;
+
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"reservation",
+            escrow_key.as_ref(),
+            &[reservation.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: reservation_vault.to_account_info(),
+            to: self.taker_ata_b.to_account_info(),
+            authority: reservation.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, reservation.bond, self.mint_b.decimals)?;
+
+        let cpi_accounts = CloseAccount {
+            account: reservation_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: reservation.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+
+    /// Escrows that require commit-reveal only fill through `reveal_take`,
+    /// by a taker whose commitment `salt` opens, once it has aged.
+    pub fn check_commitment(&self, salt: Option<&[u8; 32]>) -> Result<()> {
+        if !self.escrow.load()?.requires_commit() {
+            return Ok(());
+        }
+
+        let salt = salt.ok_or(EscrowError::CommitRequired)?;
+        let commitment = self.commitment.as_ref().ok_or(EscrowError::CommitRequired)?;
+
+        require!(
+            take_commitment(&self.taker.key(), &self.escrow.key(), salt) == commitment.commitment,
+            EscrowError::CommitmentMismatch
+        );
+
+        let clock = current_clock()?;
+
+        require!(clock.slot >= commitment.reveal_slot()?, EscrowError::RevealTooEarly);
+        require!(commitment.is_active(&clock)?, EscrowError::CommitmentExpired);
+
+        Ok(())
+    }
+
+    pub fn check_allowlist(&self, allowlist: Option<&AllowlistProof>) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        if !escrow.has_allowlist() {
+            return Ok(());
+        }
+
+        let allowlist = allowlist.ok_or(EscrowError::AllowlistProofRequired)?;
+        let leaf = allowlist_leaf(&self.taker.key(), allowlist.cap);
+
+        require!(
+            verify_proof(&escrow.taker_allowlist_root, leaf, &allowlist.proof),
+            EscrowError::NotAllowlisted
+        );
+        if let Some(cap) = allowlist.cap {
+            require!(self.vault.amount <= cap, EscrowError::AllowlistCapExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// The gate token account, if any, comes first in `remaining_accounts`,
+    /// followed by the escrow's reference keys.
+    pub fn check_remaining_accounts(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        check_take_gate(&escrow, &self.taker.key(), remaining_accounts)?;
+        check_references(&escrow, remaining_accounts)
+    }
+
+    pub fn delist(&mut self) -> Result<()> {
+        if !self.escrow.load()?.is_listed() {
+            return Ok(());
+        }
+
+        let market = self.market.as_mut().ok_or(EscrowError::MissingMarket)?;
+        market.remove(&self.escrow.key())?;
+
+        Ok(())
+    }
+
+    /// Rejects the fill if the escrow no longer matches what the taker saw.
+    pub fn check_guard(&self, expected_receive: u64, min_amount_a: u64, terms_hash: [u8; 32]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        let clock = current_clock()?;
+
+        require!(escrow.terms_hash()? == terms_hash, EscrowError::TermsHashMismatch);
+        require!(
+            escrow.current_receive(&clock) <= expected_receive,
+            EscrowError::ReceiveMismatch
+        );
+        require!(self.vault.amount >= min_amount_a, EscrowError::VaultBelowMinimum);
+
+        Ok(())
+    }
+
+    fn proceeds_account(&self) -> Result<AccountInfo<'info>> {
+        proceeds_account(
+            &self.proceeds_recipient.to_account_info(),
+            self.maker_ata_b.as_ref(),
+            &self.mint_b.key(),
+            &self.token_program.key(),
+        )
+    }
+
+    fn transfer_b(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
             from: self.taker_ata_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
+            to,
             authority: self.taker.to_account_info(),
             mint: self.mint_b.to_account_info(),
         };
 
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)
+    }
+
+    pub fn emit_take(&self, fill: &Fill) -> Result<()> {
+        emit!(TakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            amount_a: fill.amount_a,
+            amount_b: fill.amount_b,
+            referrer: self.referrer.as_ref().map(|referrer| referrer.key()),
+            referrer_fee: fill.referrer_fee,
+            memo: self.escrow.load()?.memo,
+        });
+
+        Ok(())
     }
 
     pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump]
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
         ]];
 
         let cpi_program = self.token_program.to_account_info();
@@ -110,12 +377,14 @@ impl<'info> Take<'info> {
 
         let cpi_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: self.rent_recipient.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
-        close_account(cpi_context)
+        close_account(cpi_context)?;
+
+        move_lamports(&self.escrow.to_account_info(), &self.maker.to_account_info(), escrow.bounty)
     }
 }
\ No newline at end of file
@@ -0,0 +1,230 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::{get_associated_token_address_with_program_id, AssociatedToken}, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+
+use crate::state::{Escrow, Market, Pricing};
+use crate::errors::EscrowError;
+use crate::events::TakeEvent;
+use crate::utils::{current_clock, move_lamports};
+
+/// Accounts per escrow in `remaining_accounts`:
+/// `[escrow, vault, maker, maker_ata_b, rent_recipient]`, where `maker_ata_b`
+/// is the escrow's proceeds recipient or a mint_b token account it owns.
+pub const TAKE_MANY_GROUP_LEN: usize = 5;
+
+#[derive(Accounts)]
+pub struct TakeMany<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// Book of the mint pair, required when any escrow is listed and by
+    /// `take_best`.
+    #[account(
+        mut,
+        seeds = [b"market", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Option<Account<'info, Market>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// One escrow loaded and validated from a `remaining_accounts` group.
+pub struct EscrowFill<'info> {
+    pub escrow: AccountLoader<'info, Escrow>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub maker: AccountInfo<'info>,
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub rent_recipient: AccountInfo<'info>,
+    pub amount_b: u64,
+}
+
+//Validate every escrow before moving any tokens
+//Pay each maker once, summing fills that share a maker_ata_b
+//Drain and close every vault and escrow
+impl<'info> TakeMany<'info> {
+    pub fn load_fills(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<Vec<EscrowFill<'info>>> {
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.chunks_exact(TAKE_MANY_GROUP_LEN).remainder().is_empty(),
+            EscrowError::InvalidRemainingAccounts
+        );
+
+        let clock = current_clock()?;
+
+        remaining_accounts
+            .chunks(TAKE_MANY_GROUP_LEN)
+            .map(|group| self.load_fill(group, &clock))
+            .collect()
+    }
+
+    fn load_fill(&self, group: &'info [AccountInfo<'info>], clock: &Clock) -> Result<EscrowFill<'info>> {
+        let escrow_loader = AccountLoader::<Escrow>::try_from(&group[0])?;
+        let escrow = *escrow_loader.load()?;
+        let vault = InterfaceAccount::<TokenAccount>::try_from(&group[1])?;
+        let maker = group[2].clone();
+        let maker_ata_b = InterfaceAccount::<TokenAccount>::try_from(&group[3])?;
+        let rent_recipient = group[4].clone();
+
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), EscrowError::MintMismatch);
+        require_keys_eq!(escrow.mint_b, self.mint_b.key(), EscrowError::MintMismatch);
+        require_keys_eq!(escrow.maker, maker.key(), EscrowError::InvalidRemainingAccounts);
+        require_keys_eq!(escrow.rent_payer, rent_recipient.key(), EscrowError::InvalidRemainingAccounts);
+
+        let expected_escrow = Pubkey::create_program_address(
+            &[b"escrow", maker.key.as_ref(), &escrow.seed.to_le_bytes(), &[escrow.bump]],
+            &crate::ID,
+        ).map_err(|_| error!(EscrowError::InvalidRemainingAccounts))?;
+        require_keys_eq!(expected_escrow, escrow_loader.key(), EscrowError::InvalidRemainingAccounts);
+
+        let expected_vault = get_associated_token_address_with_program_id(&escrow_loader.key(), &self.mint_a.key(), &self.token_program.key());
+        require_keys_eq!(expected_vault, vault.key(), EscrowError::VaultMismatch);
+
+        require_keys_eq!(maker_ata_b.mint, self.mint_b.key(), EscrowError::MintMismatch);
+        require!(
+            maker_ata_b.key() == escrow.proceeds_recipient || maker_ata_b.owner == escrow.proceeds_recipient,
+            EscrowError::ProceedsRecipientMismatch
+        );
+
+        require!(escrow.pricing() != Pricing::English, EscrowError::EscrowAuctioned);
+        require!(!escrow.has_allowlist(), EscrowError::AllowlistProofRequired);
+        require!(!escrow.is_gated(), EscrowError::MissingGateAccount);
+        require!(!escrow.is_standing(), EscrowError::StandingOrder);
+        require!(!escrow.has_position(), EscrowError::MissingPosition);
+        require!(!escrow.has_references(), EscrowError::MissingReference);
+        require!(!escrow.is_reserved(), EscrowError::EscrowReserved);
+        require!(!escrow.requires_commit(), EscrowError::CommitRequired);
+
+        require!(clock.slot >= escrow.unlock_slot()?, EscrowError::EscrowLocked);
+
+        let amount_b = escrow.current_receive(clock);
+
+        Ok(EscrowFill { escrow: escrow_loader, vault, maker, maker_ata_b, rent_recipient, amount_b })
+    }
+
+    /// Requires the groups to be exactly the market's best orders within the
+    /// taker's limits, in book order.
+    pub fn check_best(&self, fills: &[EscrowFill<'info>], max_orders: u8, max_amount_a: u64, max_amount_b: u64) -> Result<()> {
+        let market = self.market.as_ref().ok_or(EscrowError::MissingMarket)?;
+        let best = market.best(max_orders as usize, max_amount_a);
+
+        require!(!best.is_empty(), EscrowError::NoMarketOrders);
+        require!(
+            best.len() == fills.len() && best.iter().zip(fills).all(|(order, fill)| order.escrow == fill.escrow.key()),
+            EscrowError::InvalidRemainingAccounts
+        );
+
+        let amount_b = fills
+            .iter()
+            .try_fold(0u64, |total, fill| total.checked_add(fill.amount_b))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(amount_b <= max_amount_b, EscrowError::ReceiveMismatch);
+
+        Ok(())
+    }
+
+    pub fn delist(&mut self, fills: &[EscrowFill<'info>]) -> Result<()> {
+        for fill in fills {
+            if !fill.escrow.load()?.is_listed() {
+                continue;
+            }
+
+            let market = self.market.as_mut().ok_or(EscrowError::MissingMarket)?;
+            market.remove(&fill.escrow.key())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn pay_makers(&self, fills: &[EscrowFill<'info>]) -> Result<()> {
+        let mut payments: Vec<(&InterfaceAccount<'info, TokenAccount>, u64)> = Vec::with_capacity(fills.len());
+
+        for fill in fills {
+            match payments.iter_mut().find(|(to, _)| to.key() == fill.maker_ata_b.key()) {
+                Some((_, amount)) => *amount = amount.checked_add(fill.amount_b).ok_or(EscrowError::ArithmeticOverflow)?,
+                None => payments.push((&fill.maker_ata_b, fill.amount_b)),
+            }
+        }
+
+        for (to, amount) in payments {
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+            transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw_and_close(&self, fill: &EscrowFill<'info>) -> Result<()> {
+        let escrow = *fill.escrow.load()?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            fill.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: fill.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: fill.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, fill.vault.amount, self.mint_a.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: fill.vault.to_account_info(),
+            destination: fill.rent_recipient.clone(),
+            authority: fill.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        move_lamports(&fill.escrow.to_account_info(), &fill.maker, escrow.bounty)?;
+
+        fill.escrow.close(fill.rent_recipient.clone())?;
+
+        emit!(TakeEvent {
+            escrow: fill.escrow.key(),
+            maker: fill.maker.key(),
+            taker: self.taker.key(),
+            amount_a: fill.vault.amount,
+            amount_b: fill.amount_b,
+            referrer: None,
+            referrer_fee: 0,
+            memo: escrow.memo,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+
+use crate::state::Escrow;
+use crate::errors::EscrowError;
+use crate::events::TakeEvent;
+use crate::utils::{check_references, check_take_gate, current_clock, proceeds_account};
+
+/// Fills one `fill_amount` of a standing order. Unlike `Take`, the escrow and
+/// vault stay open for the next fill.
+#[derive(Accounts)]
+pub struct TakeStanding<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    /// Funds any token accounts created for the fill, as in `Take`.
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = rent_payer.as_ref().unwrap_or(&taker),
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the escrow's `proceeds_recipient`, as in `Take`.
+    #[account(mut, address = escrow.load()?.proceeds_recipient @ EscrowError::ProceedsRecipientMismatch)]
+    pub proceeds_recipient: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = rent_payer.as_ref().unwrap_or(&taker),
+        associated_token::mint = mint_b,
+        associated_token::authority = proceeds_recipient,
+    )]
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.load()?.seed.to_le_bytes().as_ref()],
+        bump = escrow.load()?.bump,
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Pay the maker for one fill
+//Transfer one fill from vault to taker
+//Count the fill against the remaining quantity
+impl<'info> TakeStanding<'info> {
+    pub fn check_remaining_accounts(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        check_take_gate(&escrow, &self.taker.key(), remaining_accounts)?;
+        check_references(&escrow, remaining_accounts)
+    }
+
+    pub fn take_fill(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        require!(escrow.is_standing(), EscrowError::NotStandingOrder);
+        require!(!escrow.has_allowlist(), EscrowError::AllowlistProofRequired);
+
+        let clock = current_clock()?;
+
+        require!(clock.slot >= escrow.unlock_slot()?, EscrowError::EscrowLocked);
+        require!(escrow.remaining >= escrow.fill_amount, EscrowError::StandingOrderExhausted);
+        require!(self.vault.amount >= escrow.fill_amount, EscrowError::VaultNeedsRefill);
+
+        let amount_b = escrow.current_receive(&clock);
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: proceeds_account(
+                &self.proceeds_recipient.to_account_info(),
+                self.maker_ata_b.as_ref(),
+                &self.mint_b.key(),
+                &self.token_program.key(),
+            )?,
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount_b, self.mint_b.decimals)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.key.as_ref(),
+            &escrow.seed.to_le_bytes()[..],
+            &[escrow.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, escrow.fill_amount, self.mint_a.decimals)?;
+
+        self.escrow.load_mut()?.remaining = escrow.remaining - escrow.fill_amount;
+
+        emit!(TakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            amount_a: escrow.fill_amount,
+            amount_b,
+            referrer: None,
+            referrer_fee: 0,
+            memo: escrow.memo,
+        });
+
+        Ok(())
+    }
+}
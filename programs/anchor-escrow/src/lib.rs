@@ -3,12 +3,16 @@
 
 use anchor_lang::prelude::*;
 
-mod state;
-mod instructions;
+pub mod state;
+pub mod instructions;
 mod tests;
-mod errors;
+pub mod errors;
+pub mod constants;
+pub mod events;
+pub mod utils;
 
 use instructions::*;
+use state::{DutchAuction, RecoveryConfig};
 
 declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 
@@ -16,17 +20,194 @@ declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64, lock_period:u64) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn make<'info>(ctx: Context<'_, '_, 'info, 'info, Make<'info>>, seed: u64, deposit: u64, receive: u64, lock_period:u64, collection: Option<Pubkey>, expiry: u64, bounty: u64, max_referrer_fee_bps: u16, reference: Option<EscrowReference>, recovery: Option<RecoveryConfig>) -> Result<()> {
+        ctx.accounts.validate_nft(deposit, collection)?;
         ctx.accounts.init_escrow(seed, receive, lock_period, &ctx.bumps)?;
-        ctx.accounts.deposit(deposit)
+        ctx.accounts.set_referrer_fee_cap(max_referrer_fee_bps)?;
+        ctx.accounts.set_proceeds_recipient()?;
+        ctx.accounts.set_reference(reference)?;
+        ctx.accounts.set_recovery(recovery)?;
+        ctx.accounts.attach_bounty(expiry, bounty)?;
+        ctx.accounts.mint_position()?;
+        ctx.accounts.deposit(deposit)?;
+        ctx.accounts.list_on_market(deposit, ctx.remaining_accounts)
+    }
+
+    pub fn make_dutch<'info>(ctx: Context<'_, '_, 'info, 'info, Make<'info>>, seed: u64, deposit: u64, lock_period: u64, auction: DutchAuction) -> Result<()> {
+        ctx.accounts.validate_nft(deposit, None)?;
+        ctx.accounts.init_escrow(seed, auction.start_price, lock_period, &ctx.bumps)?;
+        ctx.accounts.init_dutch_pricing(auction)?;
+        ctx.accounts.set_proceeds_recipient()?;
+        ctx.accounts.mint_position()?;
+        ctx.accounts.deposit(deposit)?;
+        ctx.accounts.list_on_market(deposit, ctx.remaining_accounts)
+    }
+
+    pub fn make_standing<'info>(ctx: Context<'_, '_, 'info, 'info, Make<'info>>, seed: u64, deposit: u64, fill_amount: u64, receive: u64, total_quantity: u64, lock_period: u64) -> Result<()> {
+        ctx.accounts.validate_nft(deposit, None)?;
+        ctx.accounts.init_escrow(seed, receive, lock_period, &ctx.bumps)?;
+        ctx.accounts.set_proceeds_recipient()?;
+        ctx.accounts.init_standing_order(deposit, fill_amount, total_quantity)?;
+        ctx.accounts.mint_position()?;
+        ctx.accounts.deposit(deposit)?;
+        ctx.accounts.list_on_market(deposit, ctx.remaining_accounts)
     }
 
     pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+        ctx.accounts.check_authority()?;
+        ctx.accounts.delist()?;
+        ctx.accounts.refund_and_close_vault()?;
+        ctx.accounts.revoke_refill_allowance()?;
+        ctx.accounts.burn_position()
+    }
+
+    pub fn rotate_recovery(ctx: Context<RotateRecovery>, recovery: Option<RecoveryConfig>) -> Result<()> {
+        ctx.accounts.rotate_recovery(recovery)
+    }
+
+    pub fn recover(ctx: Context<Recover>) -> Result<()> {
+        ctx.accounts.check_recovery()?;
+        ctx.accounts.delist()?;
+        ctx.accounts.recover_and_close_vault()
     }
 
     pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts.check_allowlist(None)?;
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.redeem_reservation()?;
+        let fill = ctx.accounts.deposit(0)?;
+        ctx.accounts.delist()?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.emit_take(&fill)
+    }
+
+    pub fn take_referred(ctx: Context<Take>, referrer_fee_bps: u16) -> Result<()> {
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts.check_allowlist(None)?;
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.redeem_reservation()?;
+        let fill = ctx.accounts.deposit(referrer_fee_bps)?;
+        ctx.accounts.delist()?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.emit_take(&fill)
+    }
+
+    pub fn take_with_guard(ctx: Context<Take>, expected_receive: u64, min_amount_a: u64, terms_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.check_guard(expected_receive, min_amount_a, terms_hash)?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts.check_allowlist(None)?;
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.redeem_reservation()?;
+        let fill = ctx.accounts.deposit(0)?;
+        ctx.accounts.delist()?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.emit_take(&fill)
+    }
+
+    pub fn take_allowlisted(ctx: Context<Take>, allowlist: AllowlistProof) -> Result<()> {
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts.check_allowlist(Some(&allowlist))?;
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.redeem_reservation()?;
+        let fill = ctx.accounts.deposit(0)?;
+        ctx.accounts.delist()?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.emit_take(&fill)
+    }
+
+    pub fn commit_take(ctx: Context<CommitTake>, commitment: [u8; 32]) -> Result<()> {
+        ctx.accounts.commit_take(commitment, &ctx.bumps)
+    }
+
+    pub fn close_commitment(_ctx: Context<CloseCommitment>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn reveal_take(ctx: Context<Take>, salt: [u8; 32], allowlist: Option<AllowlistProof>) -> Result<()> {
+        ctx.accounts.check_commitment(Some(&salt))?;
+        ctx.accounts.check_allowlist(allowlist.as_ref())?;
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.redeem_reservation()?;
+        let fill = ctx.accounts.deposit(0)?;
+        ctx.accounts.delist()?;
+        ctx.accounts.withdraw_and_close_vault()?;
+        ctx.accounts.emit_take(&fill)
+    }
+
+    pub fn set_commit_reveal(ctx: Context<SetCommitReveal>, required: bool) -> Result<()> {
+        ctx.accounts.set_commit_reveal(required)
+    }
+
+    pub fn set_taker_allowlist(ctx: Context<SetTakerAllowlist>, root: [u8; 32]) -> Result<()> {
+        ctx.accounts.set_taker_allowlist(root)
+    }
+
+    pub fn set_take_gate(ctx: Context<SetTakeGate>, gate_mint: Pubkey, min_balance: u64) -> Result<()> {
+        ctx.accounts.set_take_gate(gate_mint, min_balance)
+    }
+
+    pub fn set_reservation_terms(ctx: Context<SetReservationTerms>, bond: u64, max_slots: u64, forfeit_bps: u16) -> Result<()> {
+        ctx.accounts.set_reservation_terms(bond, max_slots, forfeit_bps)
+    }
+
+    pub fn reserve(ctx: Context<Reserve>, slots: u64) -> Result<()> {
+        ctx.accounts.reserve(slots, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn release_reservation(ctx: Context<ReleaseReservation>) -> Result<()> {
+        ctx.accounts.release()
+    }
+
+    pub fn take_many<'info>(ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>) -> Result<()> {
+        let fills = ctx.accounts.load_fills(ctx.remaining_accounts)?;
+        ctx.accounts.pay_makers(&fills)?;
+        ctx.accounts.delist(&fills)?;
+        for fill in &fills {
+            ctx.accounts.withdraw_and_close(fill)?;
+        }
+        Ok(())
+    }
+
+    pub fn take_best<'info>(ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>, max_orders: u8, max_amount_a: u64, max_amount_b: u64) -> Result<()> {
+        let fills = ctx.accounts.load_fills(ctx.remaining_accounts)?;
+        ctx.accounts.check_best(&fills, max_orders, max_amount_a, max_amount_b)?;
+        ctx.accounts.pay_makers(&fills)?;
+        ctx.accounts.delist(&fills)?;
+        for fill in &fills {
+            ctx.accounts.withdraw_and_close(fill)?;
+        }
+        Ok(())
+    }
+
+    pub fn init_market(ctx: Context<InitMarket>) -> Result<()> {
+        ctx.accounts.init_market(&ctx.bumps)
+    }
+
+    pub fn take_standing(ctx: Context<TakeStanding>) -> Result<()> {
+        ctx.accounts.check_remaining_accounts(ctx.remaining_accounts)?;
+        ctx.accounts.take_fill()
+    }
+
+    pub fn refill(ctx: Context<Refill>) -> Result<()> {
+        ctx.accounts.refill()
+    }
+
+    pub fn close_stale(ctx: Context<CloseStale>) -> Result<()> {
+        ctx.accounts.return_vault_and_close()?;
+        ctx.accounts.pay_bounty()
+    }
+
+    pub fn start_auction(ctx: Context<StartAuction>, min_bid: u64, min_increment: u64, duration: u64, extension: u64) -> Result<()> {
+        ctx.accounts.start_auction(min_bid, min_increment, duration, extension, &ctx.bumps)
+    }
+
+    pub fn bid(ctx: Context<Bid>, amount: u64) -> Result<()> {
+        ctx.accounts.bid(amount)
+    }
+
+    pub fn settle(ctx: Context<Settle>) -> Result<()> {
+        ctx.accounts.settle()
     }
 }
\ No newline at end of file
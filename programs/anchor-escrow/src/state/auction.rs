@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Auction {
+    pub escrow: Pubkey,
+    pub highest_bidder: Pubkey,
+    pub highest_bid: u64,
+    pub min_bid: u64,
+    pub min_increment: u64,
+    pub end_slot: u64,
+    pub extension: u64,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub fn has_bid(&self) -> bool {
+        self.highest_bid > 0
+    }
+
+    /// Owner of the vault contents once the auction settles.
+    pub fn winner(&self, maker: Pubkey) -> Pubkey {
+        if self.has_bid() {
+            self.highest_bidder
+        } else {
+            maker
+        }
+    }
+}
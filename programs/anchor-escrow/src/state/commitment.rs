@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+use crate::constants::{REVEAL_DELAY, REVEAL_WINDOW};
+
+/// A taker's pending `commit_take` on an escrow. Each taker holds their own,
+/// so a commitment never locks anyone else out; earlier commitments simply
+/// open for reveal first.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct TakeCommitment {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub commitment: [u8; 32],
+    pub committed_at: u64,
+    pub bump: u8,
+}
+
+impl TakeCommitment {
+    /// First slot the commitment can be revealed at.
+    pub fn reveal_slot(&self) -> Result<u64> {
+        self.committed_at
+            .checked_add(REVEAL_DELAY)
+            .ok_or(error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// A commitment can be revealed until its window closes.
+    pub fn is_active(&self, clock: &Clock) -> Result<bool> {
+        let closes_at = self
+            .reveal_slot()?
+            .checked_add(REVEAL_WINDOW)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        Ok(self.taker != Pubkey::default() && clock.slot <= closes_at)
+    }
+}
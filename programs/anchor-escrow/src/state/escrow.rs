@@ -1,14 +1,218 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
-#[account]
-#[derive(InitSpace, Debug)]
+use crate::state::{Pricing, PricingState};
+use crate::errors::EscrowError;
+use crate::constants::{MAX_REFERENCES, MIN_RECOVERY_TIMEOUT};
+
+/// Who may refund a lost maker's escrow, where to, and after how many
+/// slots without maker activity.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecoveryConfig {
+    pub authority: Pubkey,
+    /// mint_a token account that receives the recovered vault.
+    pub account: Pubkey,
+    pub timeout: u64,
+}
+
+// Fields are ordered so that every u64 stays 8-byte aligned and the trailing
+// padding is explicit, as required for a `Pod` layout.
+#[account(zero_copy)]
+#[derive(Debug)]
 pub struct Escrow {
     pub seed: u64,
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
     pub receive: u64,
-    pub bump: u8,
     pub lock_period: u64,
     pub start_time: u64,
-}
\ No newline at end of file
+    pub expires_at: u64,
+    pub bounty: u64,
+    pub pricing: PricingState,
+    pub rent_payer: Pubkey,
+    /// Owner of the mint_b ATA that receives the maker's proceeds, or a
+    /// mint_b token account that receives them directly.
+    pub proceeds_recipient: Pubkey,
+    /// Merkle root of the takers allowed to fill, all zeroes when anyone can.
+    pub taker_allowlist_root: [u8; 32],
+    /// Mint the taker must hold at least `gate_min_balance` of, or the
+    /// default pubkey when taking is not token-gated.
+    pub gate_mint: Pubkey,
+    pub gate_min_balance: u64,
+    /// mint_a sold per `take_standing`, zero for a one-shot escrow that
+    /// `take` fills and closes.
+    pub fill_amount: u64,
+    /// mint_a still offered by a standing order, in the vault or still to be
+    /// refilled from the maker's delegated ATA.
+    pub remaining: u64,
+    /// 1-of-1 mint whose holder owns the maker's side of the escrow, or the
+    /// default pubkey when the maker keeps it.
+    pub position_mint: Pubkey,
+    /// Free-form external reference such as an invoice ID.
+    pub memo: [u8; 32],
+    /// Keys every take must include as accounts so fills can be found by
+    /// reference, padded with the default pubkey.
+    pub references: [Pubkey; MAX_REFERENCES],
+    /// Default pubkey when no recovery authority is set.
+    pub recovery_authority: Pubkey,
+    pub recovery_account: Pubkey,
+    pub recovery_timeout: u64,
+    /// Slot of the maker's last instruction on this escrow.
+    pub last_activity: u64,
+    /// mint_b a taker posts to reserve the escrow, zero when reservations
+    /// are disabled.
+    pub reservation_bond: u64,
+    pub reservation_max_slots: u64,
+    /// Taker holding the escrow's `Reservation`, or the default pubkey.
+    pub reserved_by: Pubkey,
+    pub reserved_until: u64,
+    pub max_referrer_fee_bps: u16,
+    /// Share of a lapsed bond paid to the maker, the rest goes back to the
+    /// taker.
+    pub reservation_forfeit_bps: u16,
+    pub bump: u8,
+    /// Set while the escrow is in its mint pair's `Market` book.
+    pub listed: u8,
+    /// Set when takes must go through `commit_take` and `reveal_take`.
+    pub requires_commit: u8,
+    pub _padding: [u8; 1],
+}
+
+impl Escrow {
+    pub const LEN: usize = std::mem::size_of::<Escrow>();
+
+    pub fn pricing(&self) -> Pricing {
+        self.pricing.get()
+    }
+
+    pub fn set_pricing(&mut self, pricing: Pricing) {
+        self.pricing = pricing.into();
+    }
+
+    pub fn unlock_slot(&self) -> Result<u64> {
+        self.start_time
+            .checked_add(self.lock_period)
+            .ok_or(error!(EscrowError::ArithmeticOverflow))
+    }
+
+    pub fn has_allowlist(&self) -> bool {
+        self.taker_allowlist_root != [0; 32]
+    }
+
+    pub fn is_gated(&self) -> bool {
+        self.gate_mint != Pubkey::default()
+    }
+
+    pub fn is_standing(&self) -> bool {
+        self.fill_amount != 0
+    }
+
+    pub fn is_listed(&self) -> bool {
+        self.listed != 0
+    }
+
+    pub fn has_position(&self) -> bool {
+        self.position_mint != Pubkey::default()
+    }
+
+    pub fn has_references(&self) -> bool {
+        self.references.iter().any(|reference| *reference != Pubkey::default())
+    }
+
+    pub fn recovery(&self) -> Option<RecoveryConfig> {
+        (self.recovery_authority != Pubkey::default()).then_some(RecoveryConfig {
+            authority: self.recovery_authority,
+            account: self.recovery_account,
+            timeout: self.recovery_timeout,
+        })
+    }
+
+    pub fn set_recovery(&mut self, recovery: Option<RecoveryConfig>) -> Result<()> {
+        let recovery = match recovery {
+            Some(recovery) => {
+                require!(recovery.authority != Pubkey::default(), EscrowError::InvalidRecovery);
+                require!(recovery.timeout >= MIN_RECOVERY_TIMEOUT, EscrowError::InvalidRecovery);
+                recovery
+            }
+            None => RecoveryConfig { authority: Pubkey::default(), account: Pubkey::default(), timeout: 0 },
+        };
+
+        self.recovery_authority = recovery.authority;
+        self.recovery_account = recovery.account;
+        self.recovery_timeout = recovery.timeout;
+
+        Ok(())
+    }
+
+    pub fn can_recover(&self, clock: &Clock) -> Result<bool> {
+        let available_at = self
+            .last_activity
+            .checked_add(self.recovery_timeout)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        Ok(self.recovery().is_some() && clock.slot >= available_at)
+    }
+
+    pub fn is_reserved(&self) -> bool {
+        self.reserved_by != Pubkey::default()
+    }
+
+    /// Reserved and still within the reservation window.
+    pub fn reservation_active(&self, clock: &Clock) -> bool {
+        self.is_reserved() && clock.slot < self.reserved_until
+    }
+
+    pub fn requires_commit(&self) -> bool {
+        self.requires_commit != 0
+    }
+
+    pub fn is_expired(&self, clock: &Clock) -> bool {
+        self.expires_at != 0 && clock.slot >= self.expires_at
+    }
+
+    /// Hash over every term a maker can set, so a taker can pin the exact
+    /// offer they reviewed with `take_with_guard`.
+    pub fn terms_hash(&self) -> Result<[u8; 32]> {
+        let pricing = borsh::to_vec(&self.pricing()).map_err(|_| error!(EscrowError::InvalidTerms))?;
+
+        let references: Vec<u8> = self.references.iter().flat_map(|reference| reference.to_bytes()).collect();
+
+        Ok(hashv(&[
+            self.maker.as_ref(),
+            &self.seed.to_le_bytes(),
+            self.mint_a.as_ref(),
+            self.mint_b.as_ref(),
+            &self.receive.to_le_bytes(),
+            &self.lock_period.to_le_bytes(),
+            &pricing,
+            &self.expires_at.to_le_bytes(),
+            &self.bounty.to_le_bytes(),
+            &self.max_referrer_fee_bps.to_le_bytes(),
+            self.proceeds_recipient.as_ref(),
+            self.position_mint.as_ref(),
+            &self.taker_allowlist_root,
+            self.gate_mint.as_ref(),
+            &self.gate_min_balance.to_le_bytes(),
+            &self.fill_amount.to_le_bytes(),
+            &self.reservation_bond.to_le_bytes(),
+            &self.reservation_max_slots.to_le_bytes(),
+            &self.reservation_forfeit_bps.to_le_bytes(),
+            &[self.requires_commit],
+            &self.memo,
+            self.recovery_authority.as_ref(),
+            self.recovery_account.as_ref(),
+            &self.recovery_timeout.to_le_bytes(),
+            &references,
+        ])
+        .to_bytes())
+    }
+
+    /// Amount of mint_b the taker pays at the given clock.
+    pub fn current_receive(&self, clock: &Clock) -> u64 {
+        match self.pricing() {
+            Pricing::Fixed | Pricing::English => self.receive,
+            Pricing::Dutch(auction) => auction.price_at(auction.now(clock)),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_MARKET_ORDERS;
+use crate::errors::EscrowError;
+
+/// Open escrow in a market's book.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketOrder {
+    pub escrow: Pubkey,
+    pub amount_a: u64,
+    pub receive: u64,
+}
+
+impl MarketOrder {
+    /// Cheaper per unit of mint_a, compared without dividing.
+    pub fn is_better_than(&self, other: &MarketOrder) -> bool {
+        (self.receive as u128) * (other.amount_a as u128) < (other.receive as u128) * (self.amount_a as u128)
+    }
+}
+
+/// Order book of the listed escrows selling `mint_a` for `mint_b`, kept
+/// sorted best price first. Orders at the same price keep listing order.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Market {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    #[max_len(MAX_MARKET_ORDERS)]
+    pub orders: Vec<MarketOrder>,
+    pub bump: u8,
+}
+
+impl Market {
+    /// Inserts `order` at its price. A full book makes room by evicting its
+    /// worst order, which is returned, unless `order` would be the worst.
+    pub fn insert(&mut self, order: MarketOrder) -> Result<Option<MarketOrder>> {
+        let evicted = match self.orders.last() {
+            Some(worst) if self.orders.len() >= MAX_MARKET_ORDERS => {
+                require!(order.is_better_than(worst), EscrowError::MarketFull);
+                self.orders.pop()
+            }
+            _ => None,
+        };
+
+        let index = self.orders.partition_point(|listed| !order.is_better_than(listed));
+        self.orders.insert(index, order);
+
+        Ok(evicted)
+    }
+
+    pub fn remove(&mut self, escrow: &Pubkey) -> Result<MarketOrder> {
+        let index = self
+            .orders
+            .iter()
+            .position(|order| order.escrow == *escrow)
+            .ok_or(EscrowError::MissingMarket)?;
+
+        Ok(self.orders.remove(index))
+    }
+
+    /// Leading orders a taker gets for at most `max_orders` fills and
+    /// `max_amount_a` of mint_a, stopping at the first order that would
+    /// overshoot the quantity.
+    pub fn best(&self, max_orders: usize, max_amount_a: u64) -> &[MarketOrder] {
+        let mut amount_a: u64 = 0;
+        let count = self
+            .orders
+            .iter()
+            .take(max_orders)
+            .take_while(|order| match amount_a.checked_add(order.amount_a) {
+                Some(total) if total <= max_amount_a => {
+                    amount_a = total;
+                    true
+                }
+                _ => false,
+            })
+            .count();
+
+        &self.orders[..count]
+    }
+}
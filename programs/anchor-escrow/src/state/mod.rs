@@ -1,3 +1,13 @@
+pub mod auction;
+pub mod commitment;
 pub mod escrow;
+pub mod market;
+pub mod pricing;
+pub mod reservation;
 
-pub use escrow::*;
\ No newline at end of file
+pub use auction::*;
+pub use commitment::*;
+pub use escrow::*;
+pub use market::*;
+pub use pricing::*;
+pub use reservation::*;
\ No newline at end of file
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pricing {
+    Fixed,
+    Dutch(DutchAuction),
+    /// Sold to the highest bidder through an `Auction` account instead of `take`.
+    English,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeBasis {
+    Slot,
+    UnixTimestamp,
+}
+
+/// Shape of the decay between `start_price` and `floor_price`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceCurve {
+    Linear,
+    /// Quadratic, slow at first and steepest near the end.
+    EaseIn,
+    /// Quadratic, steepest at first and flattening towards the floor.
+    EaseOut,
+}
+
+/// Descending price for mint_b, decaying from `start_price` at `start` to
+/// `floor_price` at `end`, where both bounds are measured in `basis` units.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DutchAuction {
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start: u64,
+    pub end: u64,
+    pub basis: TimeBasis,
+    pub curve: PriceCurve,
+}
+
+impl DutchAuction {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.floor_price > 0, EscrowError::InvalidAuctionParams);
+        require!(self.start_price >= self.floor_price, EscrowError::InvalidAuctionParams);
+        require!(self.end > self.start, EscrowError::InvalidAuctionParams);
+
+        Ok(())
+    }
+
+    pub fn now(&self, clock: &Clock) -> u64 {
+        match self.basis {
+            TimeBasis::Slot => clock.slot,
+            TimeBasis::UnixTimestamp => clock.unix_timestamp.max(0) as u64,
+        }
+    }
+
+    pub fn price_at(&self, now: u64) -> u64 {
+        if now <= self.start {
+            return self.start_price;
+        }
+        if now >= self.end {
+            return self.floor_price;
+        }
+
+        let elapsed = (now - self.start) as u128;
+        let duration = (self.end - self.start) as u128;
+        let range = (self.start_price - self.floor_price) as u128;
+
+        // Discount already taken off start_price, always <= range
+        let discount = match self.curve {
+            PriceCurve::Linear => range * elapsed / duration,
+            PriceCurve::EaseIn => range * elapsed / duration * elapsed / duration,
+            PriceCurve::EaseOut => {
+                let remaining = duration - elapsed;
+                range - range * remaining / duration * remaining / duration
+            }
+        };
+
+        self.start_price - discount as u64
+    }
+}
+
+/// Fixed-layout form of `Pricing` stored inside the zero-copy `Escrow`.
+#[zero_copy]
+#[derive(Debug, Default)]
+pub struct PricingState {
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start: u64,
+    pub end: u64,
+    pub kind: u8,
+    pub basis: u8,
+    pub curve: u8,
+    pub _padding: [u8; 5],
+}
+
+impl PricingState {
+    const FIXED: u8 = 0;
+    const DUTCH: u8 = 1;
+    const ENGLISH: u8 = 2;
+
+    pub fn get(&self) -> Pricing {
+        match self.kind {
+            Self::DUTCH => Pricing::Dutch(DutchAuction {
+                start_price: self.start_price,
+                floor_price: self.floor_price,
+                start: self.start,
+                end: self.end,
+                basis: if self.basis == 0 { TimeBasis::Slot } else { TimeBasis::UnixTimestamp },
+                curve: match self.curve {
+                    0 => PriceCurve::Linear,
+                    1 => PriceCurve::EaseIn,
+                    _ => PriceCurve::EaseOut,
+                },
+            }),
+            Self::ENGLISH => Pricing::English,
+            _ => Pricing::Fixed,
+        }
+    }
+}
+
+impl From<Pricing> for PricingState {
+    fn from(pricing: Pricing) -> Self {
+        match pricing {
+            Pricing::Fixed => Self { kind: Self::FIXED, ..Default::default() },
+            Pricing::English => Self { kind: Self::ENGLISH, ..Default::default() },
+            Pricing::Dutch(auction) => Self {
+                start_price: auction.start_price,
+                floor_price: auction.floor_price,
+                start: auction.start,
+                end: auction.end,
+                kind: Self::DUTCH,
+                basis: auction.basis as u8,
+                curve: auction.curve as u8,
+                _padding: [0; 5],
+            },
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// A taker's hold on an escrow, backed by a mint_b bond in the
+/// reservation's ATA.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Reservation {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub bond: u64,
+    /// Forfeit share at the time of reserving, so later terms changes do
+    /// not apply to it.
+    pub forfeit_bps: u16,
+    pub expires_at: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,584 @@
+use anchor_lang::{prelude::Clock, AccountDeserialize, AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token::{self, spl_associated_token_account}, token_2022::spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount}};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use litesvm::{types::TransactionResult, LiteSVM};
+use litesvm_token::{spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_native_token::LAMPORTS_PER_SOL;
+use solana_pubkey::Pubkey;
+use solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+use std::path::PathBuf;
+
+use crate::errors::EscrowError;
+use crate::state::Escrow;
+
+/// Raw units minted to the maker in mint A and to the taker in mint B.
+pub const STARTING_BALANCE: u64 = 1_000_000_000;
+
+/// Who signs and pays for a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Actor {
+    Maker,
+    Taker,
+}
+
+/// A LiteSVM with the program loaded, a funded maker and taker, and a
+/// mint pair. The maker is the authority of both mints, holds
+/// [`STARTING_BALANCE`] of mint A and the taker the same of mint B.
+pub struct EscrowTestContext {
+    pub svm: LiteSVM,
+    pub maker: Keypair,
+    pub taker: Keypair,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub token_program: Pubkey,
+}
+
+pub struct EscrowTestContextBuilder {
+    token_program: Pubkey,
+    decimals_a: u8,
+    decimals_b: u8,
+}
+
+/// What a transaction did, whether or not it succeeded.
+#[derive(Debug)]
+pub struct TxOutcome {
+    pub result: TransactionResult,
+    pub compute_units: u64,
+    pub logs: Vec<String>,
+}
+
+impl Default for EscrowTestContextBuilder {
+    fn default() -> Self {
+        Self {
+            token_program: TOKEN_PROGRAM_ID,
+            decimals_a: 6,
+            decimals_b: 6,
+        }
+    }
+}
+
+impl EscrowTestContextBuilder {
+    /// Token or Token-2022, for both mints.
+    pub fn token_program(mut self, token_program: Pubkey) -> Self {
+        self.token_program = token_program;
+        self
+    }
+
+    pub fn decimals(mut self, decimals_a: u8, decimals_b: u8) -> Self {
+        self.decimals_a = decimals_a;
+        self.decimals_b = decimals_b;
+        self
+    }
+
+    pub fn build(self) -> EscrowTestContext {
+        let mut svm = LiteSVM::new();
+        let (maker, taker) = (Keypair::new(), Keypair::new());
+        for actor in [&maker, &taker] {
+            svm.airdrop(&actor.pubkey(), 50 * LAMPORTS_PER_SOL)
+                .expect("Failed to airdrop SOL");
+        }
+
+        let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../target/deploy/anchor_escrow.so");
+        let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+        svm.add_program(crate::ID, &program_data).expect("Failed to load program");
+
+        let mut mint = |decimals: u8, holder: &Pubkey| {
+            let mint = CreateMint::new(&mut svm, &maker)
+                .decimals(decimals)
+                .token_program_id(&self.token_program)
+                .send()
+                .unwrap();
+            let ata = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint)
+                .owner(holder)
+                .token_program_id(&self.token_program)
+                .send()
+                .unwrap();
+            MintTo::new(&mut svm, &maker, &mint, &ata, STARTING_BALANCE)
+                .token_program_id(&self.token_program)
+                .send()
+                .unwrap();
+            mint
+        };
+        let mint_a = mint(self.decimals_a, &maker.pubkey());
+        let mint_b = mint(self.decimals_b, &taker.pubkey());
+
+        EscrowTestContext {
+            svm,
+            maker,
+            taker,
+            mint_a,
+            mint_b,
+            token_program: self.token_program,
+        }
+    }
+}
+
+impl EscrowTestContext {
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> EscrowTestContextBuilder {
+        EscrowTestContextBuilder::default()
+    }
+
+    pub fn keypair(&self, actor: Actor) -> &Keypair {
+        match actor {
+            Actor::Maker => &self.maker,
+            Actor::Taker => &self.taker,
+        }
+    }
+
+    pub fn escrow(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"escrow", self.maker.pubkey().as_ref(), &seed.to_le_bytes()],
+            &crate::ID
+        ).0
+    }
+
+    pub fn vault(&self, seed: u64) -> Pubkey {
+        self.ata(&self.escrow(seed), &self.mint_a)
+    }
+
+    pub fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        associated_token::get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    /// Decoded escrow at `seed`, `None` once it is closed.
+    pub fn escrow_state(&self, seed: u64) -> Option<Escrow> {
+        let account = self.svm.get_account(&self.escrow(seed))?;
+        Escrow::try_deserialize(&mut account.data.as_ref()).ok()
+    }
+
+    /// Overwrites the escrow at `seed`, for states no instruction sequence
+    /// can reach.
+    pub fn set_escrow_state(&mut self, seed: u64, escrow: &Escrow) {
+        let address = self.escrow(seed);
+        let mut account = self.svm.get_account(&address).unwrap();
+        account.data = [Escrow::DISCRIMINATOR, bytemuck::bytes_of(escrow)].concat();
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    pub fn market(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"market", self.mint_a.as_ref(), self.mint_b.as_ref()], &crate::ID).0
+    }
+
+    pub fn position_mint(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"position", self.escrow(seed).as_ref()], &crate::ID).0
+    }
+
+    pub fn auction(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"auction", self.escrow(seed).as_ref()], &crate::ID).0
+    }
+
+    pub fn bid_vault(&self, seed: u64) -> Pubkey {
+        self.ata(&self.auction(seed), &self.mint_b)
+    }
+
+    pub fn reservation(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"reservation", self.escrow(seed).as_ref()], &crate::ID).0
+    }
+
+    pub fn commitment(&self, seed: u64, taker: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"commitment", self.escrow(seed).as_ref(), taker.as_ref()], &crate::ID).0
+    }
+
+    pub fn reservation_vault(&self, seed: u64) -> Pubkey {
+        self.ata(&self.reservation(seed), &self.mint_b)
+    }
+
+    /// Token balance of `owner`'s ATA for `mint`, zero if it does not exist.
+    pub fn balance_of(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.token_balance(&self.ata(owner, mint))
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        self.token_account(token_account).map_or(0, |account| account.amount)
+    }
+
+    pub fn token_account(&self, token_account: &Pubkey) -> Option<TokenAccount> {
+        let account = self.svm.get_account(token_account)?;
+        Some(StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap().base)
+    }
+
+    pub fn warp_slots(&mut self, slots: u64) {
+        let slot = self.svm.get_sysvar::<Clock>().slot;
+        self.svm.warp_to_slot(slot + slots);
+    }
+
+    /// Sends `ixs` signed by `actor`. Every transaction gets a fresh
+    /// blockhash, so retrying an identical one is not rejected as a
+    /// duplicate.
+    pub fn send(&mut self, ixs: &[Instruction], actor: Actor) -> TxOutcome {
+        let signer = self.keypair(actor).insecure_clone();
+        self.send_as(ixs, &[&signer])
+    }
+
+    /// Sends `ixs` signed by `signers`, the first of which pays the fee.
+    pub fn send_as(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TxOutcome {
+        self.svm.expire_blockhash();
+
+        let message = Message::new(ixs, Some(&signers[0].pubkey()));
+        let transaction = Transaction::new(signers, message, self.svm.latest_blockhash());
+
+        TxOutcome::new(self.svm.send_transaction(transaction))
+    }
+
+    /// A new wallet with 10 SOL.
+    pub fn wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        self.svm
+            .airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL");
+        wallet
+    }
+
+    /// A new mint under the context's token program with the maker as
+    /// its authority.
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        CreateMint::new(&mut self.svm, &self.maker)
+            .decimals(decimals)
+            .token_program_id(&self.token_program)
+            .send()
+            .unwrap()
+    }
+
+    /// Creates `owner`'s ATA for `mint` unless it already exists.
+    pub fn create_ata(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let ata = self.ata(owner, mint);
+        if self.svm.get_account(&ata).is_none() {
+            CreateAssociatedTokenAccount::new(&mut self.svm, &self.maker, mint)
+                .owner(owner)
+                .token_program_id(&self.token_program)
+                .send()
+                .unwrap();
+        }
+        ata
+    }
+
+    /// Mints `amount` of a maker-authority `mint` to `owner`'s ATA,
+    /// creating it if needed.
+    pub fn mint_to(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let ata = self.create_ata(owner, mint);
+        MintTo::new(&mut self.svm, &self.maker, mint, &ata, amount)
+            .token_program_id(&self.token_program)
+            .send()
+            .unwrap();
+        ata
+    }
+
+    /// A new wallet holding `amount` of mint B, like the taker.
+    pub fn funded_taker(&mut self, amount: u64) -> Keypair {
+        let taker = self.wallet();
+        let mint_b = self.mint_b;
+        self.mint_to(&taker.pubkey(), &mint_b, amount);
+        taker
+    }
+
+    /// `make` accounts for the maker's escrow at `seed`, paid for by the
+    /// maker, with every optional account left out.
+    pub fn make_accounts(&self, seed: u64) -> crate::accounts::Make {
+        let maker = self.maker.pubkey();
+
+        crate::accounts::Make {
+            maker,
+            rent_payer: None,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            maker_ata_a: self.ata(&maker, &self.mint_a),
+            mint_a_metadata: None,
+            proceeds_recipient: None,
+            escrow: self.escrow(seed),
+            vault: self.vault(seed),
+            market: None,
+            position_mint: None,
+            maker_position: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    /// Fixed-price `make` arguments with every optional term unset.
+    pub fn make_args(&self, seed: u64, deposit: u64, receive: u64, lock_period: u64) -> crate::instruction::Make {
+        crate::instruction::Make {
+            deposit,
+            seed,
+            receive,
+            lock_period,
+            collection: None,
+            expiry: 0,
+            bounty: 0,
+            max_referrer_fee_bps: 0,
+            reference: None,
+            recovery: None,
+        }
+    }
+
+    /// Fixed-price `make` by the maker at `seed`.
+    pub fn make_ix(&self, seed: u64, deposit: u64, receive: u64, lock_period: u64) -> Instruction {
+        ix(self.make_accounts(seed), self.make_args(seed, deposit, receive, lock_period))
+    }
+
+    pub fn make(&mut self, seed: u64, deposit: u64, receive: u64, lock_period: u64) -> TxOutcome {
+        let ix = self.make_ix(seed, deposit, receive, lock_period);
+        self.send(&[ix], Actor::Maker)
+    }
+
+    /// `take` accounts for `taker` filling the maker's escrow at `seed`,
+    /// paying the maker's ATA.
+    pub fn take_accounts(&self, seed: u64, taker: &Pubkey) -> crate::accounts::Take {
+        let maker = self.maker.pubkey();
+
+        crate::accounts::Take {
+            taker: *taker,
+            maker,
+            rent_payer: None,
+            rent_recipient: maker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            taker_ata_a: self.ata(taker, &self.mint_a),
+            taker_ata_b: self.ata(taker, &self.mint_b),
+            proceeds_recipient: maker,
+            maker_ata_b: Some(self.ata(&maker, &self.mint_b)),
+            referrer: None,
+            referrer_ata_b: None,
+            escrow: self.escrow(seed),
+            vault: self.vault(seed),
+            market: None,
+            position: None,
+            reservation: None,
+            reservation_vault: None,
+            commitment: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    /// Plain `take` by the taker of the maker's escrow at `seed`.
+    pub fn take_ix(&self, seed: u64) -> Instruction {
+        ix(self.take_accounts(seed, &self.taker.pubkey()), crate::instruction::Take {})
+    }
+
+    pub fn take(&mut self, seed: u64) -> TxOutcome {
+        let ix = self.take_ix(seed);
+        self.send(&[ix], Actor::Taker)
+    }
+
+    /// `take_many` accounts for `taker`, without the per-escrow groups.
+    pub fn take_many_accounts(&self, taker: &Pubkey, market: Option<Pubkey>) -> Vec<AccountMeta> {
+        crate::accounts::TakeMany {
+            taker: *taker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            taker_ata_a: self.ata(taker, &self.mint_a),
+            taker_ata_b: self.ata(taker, &self.mint_b),
+            market,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None)
+    }
+
+    /// `[escrow, vault, maker, maker_ata_b, rent_recipient]` remaining
+    /// accounts for the maker's escrow at `seed`.
+    pub fn take_many_group(&self, seed: u64) -> [AccountMeta; 5] {
+        let maker = self.maker.pubkey();
+
+        [
+            AccountMeta::new(self.escrow(seed), false),
+            AccountMeta::new(self.vault(seed), false),
+            AccountMeta::new(maker, false),
+            AccountMeta::new(self.ata(&maker, &self.mint_b), false),
+            AccountMeta::new(maker, false),
+        ]
+    }
+
+    /// `refund` accounts for `authority` closing the maker's escrow at
+    /// `seed`, with rent going back to the maker.
+    pub fn refund_accounts(&self, seed: u64, authority: &Pubkey) -> crate::accounts::Refund {
+        let maker = self.maker.pubkey();
+
+        crate::accounts::Refund {
+            authority: *authority,
+            maker,
+            rent_recipient: maker,
+            mint_a: self.mint_a,
+            authority_ata_a: self.ata(authority, &self.mint_a),
+            escrow: self.escrow(seed),
+            vault: self.vault(seed),
+            market: None,
+            position_mint: None,
+            position: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    /// `refund` by the maker of their escrow at `seed`.
+    pub fn refund_ix(&self, seed: u64) -> Instruction {
+        ix(self.refund_accounts(seed, &self.maker.pubkey()), crate::instruction::Refund {})
+    }
+
+    pub fn refund(&mut self, seed: u64) -> TxOutcome {
+        let ix = self.refund_ix(seed);
+        self.send(&[ix], Actor::Maker)
+    }
+
+    pub fn start_auction_accounts(&self, seed: u64) -> crate::accounts::StartAuction {
+        crate::accounts::StartAuction {
+            maker: self.maker.pubkey(),
+            mint_b: self.mint_b,
+            escrow: self.escrow(seed),
+            auction: self.auction(seed),
+            bid_vault: self.bid_vault(seed),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    /// `bid` accounts for `bidder`, refunding `previous_bidder` if set.
+    pub fn bid_accounts(&self, seed: u64, bidder: &Pubkey, previous_bidder: Option<&Pubkey>) -> crate::accounts::Bid {
+        crate::accounts::Bid {
+            bidder: *bidder,
+            mint_b: self.mint_b,
+            bidder_ata_b: self.ata(bidder, &self.mint_b),
+            previous_bidder_ata_b: previous_bidder.map(|previous| self.ata(previous, &self.mint_b)),
+            escrow: self.escrow(seed),
+            auction: self.auction(seed),
+            bid_vault: self.bid_vault(seed),
+            token_program: self.token_program,
+        }
+    }
+
+    /// `settle` accounts paying the maker's ATA, cranked by the maker.
+    pub fn settle_accounts(&self, seed: u64, winner: &Pubkey) -> crate::accounts::Settle {
+        let maker = self.maker.pubkey();
+
+        crate::accounts::Settle {
+            payer: maker,
+            maker,
+            rent_recipient: maker,
+            winner: *winner,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            winner_ata_a: self.ata(winner, &self.mint_a),
+            proceeds_recipient: maker,
+            maker_ata_b: Some(self.ata(&maker, &self.mint_b)),
+            escrow: self.escrow(seed),
+            auction: self.auction(seed),
+            vault: self.vault(seed),
+            bid_vault: self.bid_vault(seed),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    pub fn reserve_accounts(&self, seed: u64, taker: &Pubkey) -> crate::accounts::Reserve {
+        crate::accounts::Reserve {
+            taker: *taker,
+            maker: self.maker.pubkey(),
+            mint_b: self.mint_b,
+            taker_ata_b: self.ata(taker, &self.mint_b),
+            escrow: self.escrow(seed),
+            reservation: self.reservation(seed),
+            reservation_vault: self.reservation_vault(seed),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+
+    /// `release_reservation` accounts for `taker`'s reservation, paying
+    /// any forfeit to the maker's ATA.
+    pub fn release_accounts(&self, seed: u64, taker: &Pubkey, cranker: &Pubkey) -> crate::accounts::ReleaseReservation {
+        let maker = self.maker.pubkey();
+
+        crate::accounts::ReleaseReservation {
+            cranker: *cranker,
+            taker: *taker,
+            mint_b: self.mint_b,
+            taker_ata_b: self.ata(taker, &self.mint_b),
+            proceeds_recipient: maker,
+            maker_ata_b: Some(self.ata(&maker, &self.mint_b)),
+            escrow: self.escrow(seed),
+            reservation: self.reservation(seed),
+            reservation_vault: self.reservation_vault(seed),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: self.token_program,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+    }
+}
+
+/// An instruction for the escrow program from Anchor accounts and
+/// arguments.
+pub fn ix(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+impl TxOutcome {
+    fn new(result: TransactionResult) -> Self {
+        let meta = match &result {
+            Ok(meta) => meta,
+            Err(failed) => &failed.meta,
+        };
+
+        Self {
+            compute_units: meta.compute_units_consumed,
+            logs: meta.logs.clone(),
+            result,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Panics with the logs if the transaction failed.
+    pub fn unwrap(self) -> Self {
+        if let Err(failed) = &self.result {
+            panic!("Transaction failed with {:?}\n{}", failed.err, self.logs.join("\n"));
+        }
+        self
+    }
+
+    /// Every `E` the program emitted, in order.
+    pub fn events<E: Discriminator + AnchorDeserialize>(&self) -> Vec<E> {
+        self.logs
+            .iter()
+            .filter_map(|log| log.strip_prefix("Program data: "))
+            .filter_map(|data| STANDARD.decode(data).ok())
+            .filter_map(|bytes| {
+                let mut payload = bytes.strip_prefix(E::DISCRIMINATOR)?;
+                E::deserialize(&mut payload).ok()
+            })
+            .collect()
+    }
+
+    /// Panics unless the first instruction failed with `expected`.
+    pub fn assert_escrow_error(&self, expected: EscrowError) {
+        let err = &self.result.as_ref().expect_err("Expected transaction to fail").err;
+        assert_eq!(
+            *err,
+            TransactionError::InstructionError(0, InstructionError::Custom(u32::from(expected))),
+            "{}",
+            self.logs.join("\n")
+        );
+    }
+}
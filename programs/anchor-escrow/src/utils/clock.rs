@@ -0,0 +1,8 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+
+/// `Clock::get` with a program error instead of a bare syscall failure.
+pub fn current_clock() -> Result<Clock> {
+    Clock::get().map_err(|_| error!(EscrowError::ClockUnavailable))
+}
@@ -0,0 +1,7 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+/// Hash a taker commits to before revealing `salt` to take `escrow`.
+pub fn take_commitment(taker: &Pubkey, escrow: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[taker.as_ref(), escrow.as_ref(), salt]).to_bytes()
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// Moves lamports out of a program-owned account without a system transfer,
+/// used to pay out a bounty held on top of the escrow's rent.
+pub fn move_lamports<'info>(from: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    from.sub_lamports(amount)?;
+    to.add_lamports(amount)?;
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// Leaves and inner nodes are hashed under different prefixes so a proof
+// cannot pass an inner node off as a leaf.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf committing to a taker and the most mint_a they may take from one
+/// escrow, if capped.
+pub fn allowlist_leaf(taker: &Pubkey, cap: Option<u64>) -> [u8; 32] {
+    let mut cap_bytes = [0u8; 9];
+    if let Some(cap) = cap {
+        cap_bytes[0] = 1;
+        cap_bytes[1..].copy_from_slice(&cap.to_le_bytes());
+    }
+
+    hashv(&[LEAF_PREFIX, taker.as_ref(), &cap_bytes]).to_bytes()
+}
+
+// Pairs are sorted before hashing, so proofs carry no left/right flags.
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling)) == *root
+}
+
+/// Off-chain builder for allowlist roots and proofs. A node without a
+/// sibling is carried up to the next layer unchanged.
+#[cfg(test)]
+pub struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+#[cfg(test)]
+impl MerkleTree {
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        assert!(!leaves.is_empty(), "Merkle tree needs at least one leaf");
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    pub fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions},
+};
+use spl_token_group_interface::state::TokenGroupMember;
+
+use crate::errors::EscrowError;
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+const METADATA_V1_KEY: u8 = 4;
+
+// Metaplex `Metadata` is Borsh encoded with variable length strings and
+// vectors ahead of the collection field, so it is walked field by field
+// rather than pulling in the full mpl-token-metadata crate.
+struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.offset.checked_add(len).ok_or(EscrowError::InvalidNftMetadata)?;
+        let bytes = self.data.get(self.offset..end).ok_or(EscrowError::InvalidNftMetadata)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Pubkey::try_from(self.take(32)?).map_err(|_| error!(EscrowError::InvalidNftMetadata))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?.try_into().map_err(|_| error!(EscrowError::InvalidNftMetadata))?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        let len = self.u32()?;
+        self.take(len as usize)?;
+        Ok(())
+    }
+
+    fn is_some(&mut self) -> Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => err!(EscrowError::InvalidNftMetadata),
+        }
+    }
+}
+
+/// Returns the verified collection of a Metaplex metadata account, if any.
+pub fn metaplex_verified_collection(metadata: &AccountInfo, mint: &Pubkey) -> Result<Option<Pubkey>> {
+    require_keys_eq!(*metadata.owner, TOKEN_METADATA_PROGRAM_ID, EscrowError::InvalidNftMetadata);
+
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    );
+    require_keys_eq!(metadata.key(), expected, EscrowError::InvalidNftMetadata);
+
+    let data = metadata.try_borrow_data()?;
+    let mut cursor = Cursor { data: &data, offset: 0 };
+
+    require!(cursor.u8()? == METADATA_V1_KEY, EscrowError::InvalidNftMetadata);
+    cursor.take(32)?; // update_authority
+    require_keys_eq!(cursor.pubkey()?, *mint, EscrowError::InvalidNftMetadata);
+    cursor.skip_string()?; // name
+    cursor.skip_string()?; // symbol
+    cursor.skip_string()?; // uri
+    cursor.take(2)?; // seller_fee_basis_points
+    if cursor.is_some()? {
+        let creators = cursor.u32()?;
+        cursor.take((creators as usize).checked_mul(34).ok_or(EscrowError::InvalidNftMetadata)?)?;
+    }
+    cursor.take(2)?; // primary_sale_happened, is_mutable
+    if cursor.is_some()? {
+        cursor.take(1)?; // edition_nonce
+    }
+    if cursor.is_some()? {
+        cursor.take(1)?; // token_standard
+    }
+    if !cursor.is_some()? {
+        return Ok(None);
+    }
+
+    let verified = cursor.u8()? == 1;
+    let key = cursor.pubkey()?;
+
+    Ok(verified.then_some(key))
+}
+
+/// Returns the token group a Token-2022 mint is a member of. The mint must
+/// carry a metadata pointer to itself and keep its group member data in-mint.
+pub fn token_2022_collection(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)
+        .map_err(|_| error!(EscrowError::InvalidNftMetadata))?;
+
+    let pointer = state
+        .get_extension::<MetadataPointer>()
+        .map_err(|_| error!(EscrowError::MissingNftMetadata))?;
+    require!(
+        Option::<Pubkey>::from(pointer.metadata_address) == Some(mint.key()),
+        EscrowError::InvalidNftMetadata
+    );
+
+    match state.get_extension::<TokenGroupMember>() {
+        Ok(member) => {
+            require_keys_eq!(member.mint, mint.key(), EscrowError::InvalidNftMetadata);
+            Ok(Some(member.group))
+        }
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,13 @@
+pub mod clock;
+pub mod commitment;
+pub mod lamports;
+pub mod merkle;
+pub mod metadata;
+pub mod token_accounts;
+
+pub use clock::*;
+pub use commitment::*;
+pub use lamports::*;
+pub use merkle::*;
+pub use metadata::*;
+pub use token_accounts::*;
\ No newline at end of file
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::errors::EscrowError;
+use crate::state::Escrow;
+
+/// Token account the maker's mint_b proceeds are paid into: the escrow's
+/// `proceeds_recipient` itself when it is a mint_b token account, otherwise
+/// its ATA passed as `maker_ata_b`.
+pub fn proceeds_account<'info>(
+    proceeds_recipient: &AccountInfo<'info>,
+    maker_ata_b: Option<&InterfaceAccount<'info, TokenAccount>>,
+    mint_b: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<AccountInfo<'info>> {
+    if proceeds_recipient.owner == token_program {
+        let account = TokenAccount::try_deserialize(&mut &proceeds_recipient.try_borrow_data()?[..])?;
+        require_keys_eq!(account.mint, *mint_b, EscrowError::MintMismatch);
+        return Ok(proceeds_recipient.clone());
+    }
+
+    maker_ata_b
+        .map(|maker_ata_b| maker_ata_b.to_account_info())
+        .ok_or(error!(EscrowError::MissingProceedsAccount))
+}
+
+/// Who is entitled to an escrow's proceeds and refund: the holder of its
+/// position token when it has one, otherwise the maker.
+pub fn position_holder(escrow: &Escrow, position: Option<&InterfaceAccount<TokenAccount>>) -> Result<Pubkey> {
+    if !escrow.has_position() {
+        return Ok(escrow.maker);
+    }
+
+    let position = position.ok_or(EscrowError::MissingPosition)?;
+    require_keys_eq!(position.mint, escrow.position_mint, EscrowError::InvalidPosition);
+    require!(position.amount == 1, EscrowError::InvalidPosition);
+
+    Ok(position.owner)
+}
+
+/// Requires every reference key of the escrow among the remaining accounts,
+/// passed read-only.
+pub fn check_references(escrow: &Escrow, remaining_accounts: &[AccountInfo]) -> Result<()> {
+    for reference in escrow.references.iter().filter(|reference| **reference != Pubkey::default()) {
+        let account = remaining_accounts
+            .iter()
+            .find(|account| account.key == reference)
+            .ok_or(EscrowError::MissingReference)?;
+        require!(!account.is_writable, EscrowError::WritableReference);
+    }
+
+    Ok(())
+}
+
+/// Checks the taker's gate token account, passed as the first remaining
+/// account, when the escrow is token-gated.
+pub fn check_take_gate(escrow: &Escrow, taker: &Pubkey, remaining_accounts: &[AccountInfo]) -> Result<()> {
+    if !escrow.is_gated() {
+        return Ok(());
+    }
+
+    let gate_account = remaining_accounts.first().ok_or(EscrowError::MissingGateAccount)?;
+    require!(
+        *gate_account.owner == anchor_spl::token::ID || *gate_account.owner == anchor_spl::token_2022::ID,
+        EscrowError::InvalidGateAccount
+    );
+
+    let gate_account = TokenAccount::try_deserialize(&mut &gate_account.try_borrow_data()?[..])?;
+    require_keys_eq!(gate_account.mint, escrow.gate_mint, EscrowError::InvalidGateAccount);
+    require_keys_eq!(gate_account.owner, *taker, EscrowError::InvalidGateAccount);
+    require!(gate_account.amount >= escrow.gate_min_balance, EscrowError::GateBalanceTooLow);
+
+    Ok(())
+}
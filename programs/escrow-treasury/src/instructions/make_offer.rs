@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_escrow::{cpi::accounts::Make, program::AnchorEscrow};
+
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    /// Owner of the treasury. Pays the escrow and vault rent, which the
+    /// escrow program returns to it when the escrow closes.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury", authority.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_ata_a: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: created and checked by the escrow program
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+    /// CHECK: created and checked by the escrow program
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    pub escrow_program: Program<'info, AnchorEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeOffer<'info> {
+    /// Escrows `deposit` of the treasury's mint_a for `receive` of mint_b,
+    /// signing as the treasury PDA.
+    pub fn make_offer(&mut self, seed: u64, deposit: u64, receive: u64, bumps: &MakeOfferBumps) -> Result<()> {
+        let authority = self.authority.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"treasury",
+            authority.as_ref(),
+            &[bumps.treasury]
+        ]];
+
+        let cpi_accounts = Make {
+            maker: self.treasury.to_account_info(),
+            rent_payer: Some(self.authority.to_account_info()),
+            mint_a: self.mint_a.to_account_info(),
+            mint_b: self.mint_b.to_account_info(),
+            maker_ata_a: self.treasury_ata_a.to_account_info(),
+            mint_a_metadata: None,
+            proceeds_recipient: None,
+            escrow: self.escrow.to_account_info(),
+            vault: self.vault.to_account_info(),
+            market: None,
+            position_mint: None,
+            maker_position: None,
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.escrow_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        anchor_escrow::cpi::make(cpi_ctx, seed, deposit, receive, 0, None, 0, 0, 0, None, None)
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_escrow::{cpi::accounts::Refund, program::AnchorEscrow};
+
+#[derive(Accounts)]
+pub struct RefundOffer<'info> {
+    /// Owner of the treasury. Gets back the rent it paid in `make_offer`.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury", authority.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_ata_a: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: checked by the escrow program
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+    /// CHECK: checked by the escrow program
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    pub escrow_program: Program<'info, AnchorEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundOffer<'info> {
+    /// Returns the vault of one of the treasury's escrows to the treasury.
+    pub fn refund_offer(&mut self, bumps: &RefundOfferBumps) -> Result<()> {
+        let authority = self.authority.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"treasury",
+            authority.as_ref(),
+            &[bumps.treasury]
+        ]];
+
+        let cpi_accounts = Refund {
+            authority: self.treasury.to_account_info(),
+            maker: self.treasury.to_account_info(),
+            rent_recipient: self.authority.to_account_info(),
+            mint_a: self.mint_a.to_account_info(),
+            authority_ata_a: self.treasury_ata_a.to_account_info(),
+            escrow: self.escrow.to_account_info(),
+            vault: self.vault.to_account_info(),
+            market: None,
+            position_mint: None,
+            position: None,
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.escrow_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        anchor_escrow::cpi::refund(cpi_ctx)
+    }
+}
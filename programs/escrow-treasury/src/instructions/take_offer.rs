@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_escrow::{cpi::accounts::Take, program::AnchorEscrow};
+
+#[derive(Accounts)]
+pub struct TakeOffer<'info> {
+    /// Owner of the treasury, pays for any token accounts the take creates.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury", authority.key().as_ref()],
+        bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+    /// CHECK: the escrow's maker, checked by the escrow program
+    #[account(mut)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK: the escrow's rent payer, checked by the escrow program
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    /// CHECK: created if needed and checked by the escrow program
+    #[account(mut)]
+    pub treasury_ata_a: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the escrow's proceeds recipient, checked by the escrow program
+    #[account(mut)]
+    pub proceeds_recipient: UncheckedAccount<'info>,
+    /// CHECK: created if needed and checked by the escrow program
+    #[account(mut)]
+    pub maker_ata_b: UncheckedAccount<'info>,
+    /// CHECK: checked by the escrow program
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+    /// CHECK: checked by the escrow program
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    pub escrow_program: Program<'info, AnchorEscrow>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeOffer<'info> {
+    /// Fills a plain fixed-price escrow with the treasury's mint_b.
+    pub fn take_offer(&mut self, bumps: &TakeOfferBumps) -> Result<()> {
+        let authority = self.authority.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"treasury",
+            authority.as_ref(),
+            &[bumps.treasury]
+        ]];
+
+        let cpi_accounts = Take {
+            taker: self.treasury.to_account_info(),
+            maker: self.maker.to_account_info(),
+            rent_payer: Some(self.authority.to_account_info()),
+            rent_recipient: self.rent_recipient.to_account_info(),
+            mint_a: self.mint_a.to_account_info(),
+            mint_b: self.mint_b.to_account_info(),
+            taker_ata_a: self.treasury_ata_a.to_account_info(),
+            taker_ata_b: self.treasury_ata_b.to_account_info(),
+            proceeds_recipient: self.proceeds_recipient.to_account_info(),
+            maker_ata_b: Some(self.maker_ata_b.to_account_info()),
+            referrer: None,
+            referrer_ata_b: None,
+            escrow: self.escrow.to_account_info(),
+            vault: self.vault.to_account_info(),
+            market: None,
+            position: None,
+            reservation: None,
+            reservation_vault: None,
+            commitment: None,
+            associated_token_program: self.associated_token_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.escrow_program.to_account_info(), cpi_accounts, &signer_seeds);
+
+        anchor_escrow::cpi::take(cpi_ctx)
+    }
+}
@@ -0,0 +1,30 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+
+mod instructions;
+mod tests;
+
+use instructions::*;
+
+declare_id!("HaBBsUcGz6jbd4GddAQ3p8gbLg5te9vZPjQsxTRGyizL");
+
+/// Holds tokens for `authority` in a data-less PDA and trades them through
+/// `anchor_escrow`, with the PDA as maker and taker.
+#[program]
+pub mod escrow_treasury {
+    use super::*;
+
+    pub fn make_offer(ctx: Context<MakeOffer>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
+        ctx.accounts.make_offer(seed, deposit, receive, &ctx.bumps)
+    }
+
+    pub fn take_offer(ctx: Context<TakeOffer>) -> Result<()> {
+        ctx.accounts.take_offer(&ctx.bumps)
+    }
+
+    pub fn refund_offer(ctx: Context<RefundOffer>) -> Result<()> {
+        ctx.accounts.refund_offer(&ctx.bumps)
+    }
+}
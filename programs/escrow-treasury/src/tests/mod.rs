@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use {
+        anchor_lang::{
+            prelude::msg,
+            solana_program::program_pack::Pack,
+            InstructionData,
+            ToAccountMetas
+        }, anchor_spl::{
+            associated_token::{
+                self,
+                spl_associated_token_account
+            },
+            token::spl_token
+        },
+        litesvm::LiteSVM,
+        litesvm_token::{
+            spl_token::ID as TOKEN_PROGRAM_ID,
+            CreateAssociatedTokenAccount,
+            CreateMint, MintTo
+        },
+        solana_instruction::Instruction,
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_native_token::LAMPORTS_PER_SOL,
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+        solana_signer::Signer,
+        solana_transaction::Transaction,
+        std::path::PathBuf
+    };
+
+    static PROGRAM_ID: Pubkey = crate::ID;
+    static ESCROW_PROGRAM_ID: Pubkey = anchor_escrow::ID;
+
+    fn setup() -> (LiteSVM, Keypair) {
+        let mut program = LiteSVM::new();
+        let payer = Keypair::new();
+
+        program
+            .airdrop(&payer.pubkey(), 50 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to payer");
+
+        // Both programs are built by `anchor build` into the workspace target
+        let deploy_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy");
+        for (program_id, so_file) in [(PROGRAM_ID, "escrow_treasury.so"), (ESCROW_PROGRAM_ID, "anchor_escrow.so")] {
+            let program_data = std::fs::read(deploy_dir.join(so_file)).expect("Failed to read program SO file");
+            let _ = program.add_program(program_id, &program_data);
+        }
+
+        (program, payer)
+    }
+
+    fn send(program: &mut LiteSVM, ixs: &[Instruction], signer: &Keypair) -> litesvm::types::TransactionResult {
+        let message = Message::new(ixs, Some(&signer.pubkey()));
+        let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+        program.send_transaction(transaction)
+    }
+
+    fn balance(program: &LiteSVM, account: &Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&program.get_account(account).unwrap().data).unwrap().amount
+    }
+
+    fn escrow(maker: &Pubkey, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &ESCROW_PROGRAM_ID).0
+    }
+
+    fn make_offer_ix(authority: Pubkey, treasury: Pubkey, mint_a: Pubkey, mint_b: Pubkey, seed: u64, deposit: u64, receive: u64) -> Instruction {
+        let escrow = escrow(&treasury, seed);
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::MakeOffer {
+                authority: authority,
+                treasury: treasury,
+                mint_a: mint_a,
+                mint_b: mint_b,
+                treasury_ata_a: associated_token::get_associated_token_address(&treasury, &mint_a),
+                escrow: escrow,
+                vault: associated_token::get_associated_token_address(&escrow, &mint_a),
+                escrow_program: ESCROW_PROGRAM_ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: crate::instruction::MakeOffer { seed, deposit, receive }.data(),
+        }
+    }
+
+    #[test]
+    fn test_treasury_makes_takes_and_refunds_via_cpi() {
+        let (mut program, payer) = setup();
+        let authority = payer.pubkey();
+        let treasury = Pubkey::find_program_address(&[b"treasury", authority.as_ref()], &PROGRAM_ID).0;
+        let trader = Keypair::new();
+        program
+            .airdrop(&trader.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to trader");
+
+        let mint_a = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&authority)
+            .send()
+            .unwrap();
+        let mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&authority)
+            .send()
+            .unwrap();
+        let treasury_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&treasury).send().unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &treasury_ata_a, 100)
+            .send()
+            .unwrap();
+        let trader_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&trader.pubkey()).send().unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &trader_ata_a, 100)
+            .send()
+            .unwrap();
+        let trader_ata_b = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_b)
+            .owner(&trader.pubkey()).send().unwrap();
+        MintTo::new(&mut program, &payer, &mint_b, &trader_ata_b, 100)
+            .send()
+            .unwrap();
+        let treasury_ata_b = associated_token::get_associated_token_address(&treasury, &mint_b);
+
+        // The treasury PDA makes an escrow and refunds it
+        let escrow_1 = escrow(&treasury, 1);
+        let tx = send(&mut program, &[make_offer_ix(authority, treasury, mint_a, mint_b, 1, 10, 20)], &payer).unwrap();
+        msg!("make_offer: {:?}", tx.logs);
+        assert_eq!(balance(&program, &associated_token::get_associated_token_address(&escrow_1, &mint_a)), 10);
+        assert_eq!(balance(&program, &treasury_ata_a), 90);
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::RefundOffer {
+                authority: authority,
+                treasury: treasury,
+                mint_a: mint_a,
+                treasury_ata_a: treasury_ata_a,
+                escrow: escrow_1,
+                vault: associated_token::get_associated_token_address(&escrow_1, &mint_a),
+                escrow_program: ESCROW_PROGRAM_ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: crate::instruction::RefundOffer {}.data(),
+        };
+        send(&mut program, &[refund_ix], &payer).unwrap();
+        assert_eq!(balance(&program, &treasury_ata_a), 100);
+        assert!(program.get_account(&escrow_1).is_none(), "Expected escrow to be closed");
+
+        // A regular taker fills the treasury's escrow and the proceeds land in its ATA
+        let escrow_2 = escrow(&treasury, 2);
+        send(&mut program, &[make_offer_ix(authority, treasury, mint_a, mint_b, 2, 10, 20)], &payer).unwrap();
+        let take_ix = Instruction {
+            program_id: ESCROW_PROGRAM_ID,
+            accounts: anchor_escrow::accounts::Take {
+                taker: trader.pubkey(),
+                maker: treasury,
+                rent_payer: None,
+                rent_recipient: authority,
+                mint_a: mint_a,
+                mint_b: mint_b,
+                taker_ata_a: trader_ata_a,
+                taker_ata_b: trader_ata_b,
+                proceeds_recipient: treasury,
+                maker_ata_b: Some(treasury_ata_b),
+                referrer: None,
+                referrer_ata_b: None,
+                escrow: escrow_2,
+                vault: associated_token::get_associated_token_address(&escrow_2, &mint_a),
+                market: None,
+                position: None,
+                reservation: None,
+                reservation_vault: None,
+                commitment: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::Take {}.data(),
+        };
+        send(&mut program, &[take_ix], &trader).unwrap();
+        assert_eq!(balance(&program, &treasury_ata_b), 20);
+        assert_eq!(balance(&program, &trader_ata_a), 110);
+
+        // The treasury PDA takes the trader's escrow with those proceeds
+        let escrow_3 = escrow(&trader.pubkey(), 3);
+        let make_ix = Instruction {
+            program_id: ESCROW_PROGRAM_ID,
+            accounts: anchor_escrow::accounts::Make {
+                maker: trader.pubkey(),
+                rent_payer: None,
+                mint_a: mint_a,
+                mint_b: mint_b,
+                maker_ata_a: trader_ata_a,
+                mint_a_metadata: None,
+                proceeds_recipient: None,
+                escrow: escrow_3,
+                vault: associated_token::get_associated_token_address(&escrow_3, &mint_a),
+                market: None,
+                position_mint: None,
+                maker_position: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::Make {
+                seed: 3,
+                deposit: 5,
+                receive: 15,
+                lock_period: 0,
+                collection: None,
+                expiry: 0,
+                bounty: 0,
+                max_referrer_fee_bps: 0,
+                reference: None,
+                recovery: None,
+            }.data(),
+        };
+        send(&mut program, &[make_ix], &trader).unwrap();
+
+        let take_offer_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::TakeOffer {
+                authority: authority,
+                treasury: treasury,
+                maker: trader.pubkey(),
+                rent_recipient: trader.pubkey(),
+                mint_a: mint_a,
+                mint_b: mint_b,
+                treasury_ata_a: treasury_ata_a,
+                treasury_ata_b: treasury_ata_b,
+                proceeds_recipient: trader.pubkey(),
+                maker_ata_b: trader_ata_b,
+                escrow: escrow_3,
+                vault: associated_token::get_associated_token_address(&escrow_3, &mint_a),
+                escrow_program: ESCROW_PROGRAM_ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: crate::instruction::TakeOffer {}.data(),
+        };
+        let tx = send(&mut program, &[take_offer_ix], &payer).unwrap();
+        msg!("take_offer: {:?}", tx.logs);
+        assert_eq!(balance(&program, &treasury_ata_a), 95);
+        assert_eq!(balance(&program, &treasury_ata_b), 5);
+        assert_eq!(balance(&program, &trader_ata_b), 95);
+        assert!(program.get_account(&escrow_3).is_none(), "Expected escrow to be closed");
+    }
+}